@@ -1,30 +1,268 @@
-use crate::services::MusicService;
-use crate::ui::{music_buttons::MusicButtons, music_path_entry_ui::MusicPathEntryUI};
+use std::{
+    sync::mpsc::Receiver,
+    time::{Duration, Instant},
+};
+
+use crate::models::track::MusicState;
+use crate::services::{
+    crossfade::CrossfadeSettings, equalizer::EqualizerState, events::PlaybackEvent,
+    player::Player, queue::PlaybackMode, replay_gain::ReplayGainSettings,
+    resume_points::ResumePoints, volume::Volume,
+};
+use crate::ui::{
+    artwork_ui::ArtworkUI, batch_editor_ui::BatchEditorUI, equalizer_ui::EqualizerUI,
+    music_buttons::MusicButtons, music_path_entry_ui::MusicPathEntryUI, settings_ui::SettingsUI,
+    sleep_timer_ui::SleepTimerUI, tag_editor_ui::TagEditorUI,
+};
 use eframe::egui::{self, ScrollArea, TextureHandle};
 pub struct MusicPlayer {
-    music_service: MusicService,
+    player: Player,
     music_path_entry_ui: MusicPathEntryUI,
     music_button_ui: MusicButtons,
+    settings_ui: SettingsUI,
+    equalizer_ui: EqualizerUI,
+    sleep_timer_ui: SleepTimerUI,
+    tag_editor_ui: TagEditorUI,
+    batch_editor_ui: BatchEditorUI,
+    artwork_ui: ArtworkUI,
     cover_texture: Option<TextureHandle>,
-    pos: u64,
-    total_duration: Option<u64>,
+    events: Receiver<PlaybackEvent>,
+    pos: Duration,
+    total_duration: Option<Duration>,
+    /// Whether the track is running, so `pos` can advance between position ticks.
+    playing: bool,
+    /// When `pos` was last brought up to date.
+    synced_at: Instant,
+    /// True while the user drags the timeline, so position ticks don't fight them.
+    seeking: bool,
     music_list: Vec<String>, // timestamp_text: String,
 }
 
+const VOLUME_KEY: &str = "volume";
+const EQUALIZER_KEY: &str = "equalizer";
+const PLAYBACK_MODE_KEY: &str = "playback_mode";
+const OUTPUT_DEVICE_KEY: &str = "output_device";
+const RESUME_POINTS_KEY: &str = "resume_points";
+const CROSSFADE_KEY: &str = "crossfade";
+const REPLAY_GAIN_KEY: &str = "replay_gain";
+
 impl MusicPlayer {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut player = Player::new();
+        let events = player.subscribe();
+        if let Some(storage) = cc.storage {
+            if let Some(volume) = eframe::get_value::<Volume>(storage, VOLUME_KEY) {
+                player.set_volume_state(volume);
+            }
+            if let Some(equalizer) = eframe::get_value::<EqualizerState>(storage, EQUALIZER_KEY) {
+                player.set_equalizer_state(equalizer);
+            }
+            if let Some(mode) = eframe::get_value::<PlaybackMode>(storage, PLAYBACK_MODE_KEY) {
+                player.set_playback_mode(mode);
+            }
+            if let Some(device) = eframe::get_value::<Option<String>>(storage, OUTPUT_DEVICE_KEY) {
+                player.set_output_device(device);
+            }
+            if let Some(points) = eframe::get_value::<ResumePoints>(storage, RESUME_POINTS_KEY) {
+                player.set_resume_points(points);
+            }
+            if let Some(crossfade) = eframe::get_value::<CrossfadeSettings>(storage, CROSSFADE_KEY)
+            {
+                player.set_crossfade(crossfade);
+            }
+            if let Some(replay_gain) =
+                eframe::get_value::<ReplayGainSettings>(storage, REPLAY_GAIN_KEY)
+            {
+                player.set_replay_gain(replay_gain);
+            }
+        }
+
         Self {
             music_path_entry_ui: MusicPathEntryUI::new(),
-            music_service: MusicService::new(),
+            player,
             music_button_ui: MusicButtons::new(),
+            settings_ui: SettingsUI::new(),
+            equalizer_ui: EqualizerUI::new(),
+            sleep_timer_ui: SleepTimerUI::new(),
+            tag_editor_ui: TagEditorUI::new(),
+            batch_editor_ui: BatchEditorUI::new(),
+            artwork_ui: ArtworkUI::new(),
             cover_texture: None,
-            pos: 0,
+            events,
+            pos: Duration::ZERO,
             total_duration: None,
+            playing: false,
+            synced_at: Instant::now(),
+            seeking: false,
             music_list: Vec::new(),
             // timestamp_text: String,
         }
     }
 
+    fn show_loop_controls(&mut self, ui: &mut egui::Ui) {
+        let ab_loop = self.player.view().ab_loop;
+
+        if self
+            .music_button_ui
+            .show_loop_button(ui, "A", ab_loop.a.is_some())
+            .on_hover_text("Set loop start [")
+            .clicked()
+        {
+            self.player.set_loop_a();
+        }
+        if self
+            .music_button_ui
+            .show_loop_button(ui, "B", ab_loop.b.is_some())
+            .on_hover_text("Set loop end ]")
+            .clicked()
+        {
+            self.player.set_loop_b();
+        }
+        if self
+            .music_button_ui
+            .show_loop_button(ui, "✖", false)
+            .on_hover_text("Clear loop \\")
+            .clicked()
+        {
+            self.player.clear_ab_loop();
+        }
+    }
+
+    /// "Artist — Album (year)" under the title, with every other tag on hover.
+    fn show_track_details(&self, ui: &mut egui::Ui) {
+        let metadata = &self.player.view().metadata;
+        let byline: Vec<String> = [metadata.artist_line(), metadata.album_line()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if byline.is_empty() {
+            return;
+        }
+
+        let details = metadata
+            .details()
+            .into_iter()
+            .map(|(label, value)| format!("{}: {}", label, value))
+            .collect::<Vec<_>>()
+            .join("\n");
+        ui.weak(byline.join(" — ")).on_hover_text(details);
+    }
+
+    /// "Resume from 43:12?" for a track that was left off part-way.
+    fn show_resume_offer(&mut self, ui: &mut egui::Ui) {
+        let Some(pos) = self.player.view().resume_offer else {
+            return;
+        };
+        let precise = self
+            .total_duration
+            .is_some_and(|total| total < MusicButtons::SHORT_CLIP);
+        let label = format!("⏵ Resume from {}?", self.music_button_ui.format_time(pos, precise));
+
+        ui.horizontal(|ui| {
+            if ui.button(label).clicked() {
+                self.player.accept_resume_offer();
+            }
+            if ui.small_button("✖").on_hover_text("Start from the beginning").clicked() {
+                self.player.dismiss_resume_offer();
+            }
+        });
+    }
+
+    fn handle_events(&mut self) {
+        let now = Instant::now();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                PlaybackEvent::TrackLoaded { duration, .. } => {
+                    self.total_duration = duration;
+                    self.pos = Duration::ZERO;
+                }
+                PlaybackEvent::PositionTick(pos) if !self.seeking => self.pos = pos,
+                PlaybackEvent::Seeked(pos) => self.pos = pos,
+                PlaybackEvent::Playing => self.playing = true,
+                PlaybackEvent::Paused => self.playing = false,
+                PlaybackEvent::Stopped => {
+                    self.playing = false;
+                    self.pos = Duration::ZERO;
+                }
+                _ => {}
+            }
+        }
+
+        // Ticks only arrive every quarter second; fill in the frames between them.
+        if self.playing && !self.seeking {
+            self.pos += now
+                .duration_since(self.synced_at)
+                .mul_f32(self.player.view().speed);
+            if let Some(total) = self.total_duration {
+                self.pos = self.pos.min(total);
+            }
+        }
+        self.synced_at = now;
+    }
+
+    /// Shows the last playback error with its causes until dismissed.
+    fn show_error(&mut self, ui: &mut egui::Ui) {
+        let Some(error) = self.player.view().last_error.clone() else {
+            return;
+        };
+
+        let mut message = error.to_string();
+        let mut cause = std::error::Error::source(&error);
+        while let Some(e) = cause {
+            message.push_str(&format!("\n  caused by: {}", e));
+            cause = e.source();
+        }
+
+        let mut dismissed = false;
+        egui::Frame::group(ui.style())
+            .stroke(egui::Stroke::new(1.0, ui.visuals().error_fg_color))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {}", message));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                        dismissed = ui.small_button("✖").on_hover_text("Dismiss").clicked();
+                    });
+                });
+            });
+        if dismissed {
+            self.player.clear_error();
+        }
+        ui.add_space(5.0);
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // Don't steal keys while the user is typing a path or preset name.
+        if ctx.wants_keyboard_input() || !self.player.view().music_loaded {
+            return;
+        }
+        ctx.input(|i| {
+            if i.key_pressed(egui::Key::OpenBracket) {
+                self.player.set_loop_a();
+            }
+            if i.key_pressed(egui::Key::CloseBracket) {
+                self.player.set_loop_b();
+            }
+            if i.key_pressed(egui::Key::Backslash) {
+                self.player.clear_ab_loop();
+            }
+
+            // Arrow keys skip 5 s, 1 s with Shift, 30 s with Ctrl/Cmd.
+            let step = if i.modifiers.command {
+                Duration::from_secs(30)
+            } else if i.modifiers.shift {
+                Duration::from_secs(1)
+            } else {
+                Duration::from_secs(5)
+            };
+            if i.key_pressed(egui::Key::ArrowRight) {
+                self.player.seek_forward(step);
+            }
+            if i.key_pressed(egui::Key::ArrowLeft) {
+                self.player.seek_backward(step);
+            }
+        });
+    }
+
     pub fn display_music_list(&self, ui: &mut egui::Ui) {
         if !self.music_list.is_empty() {
             return;
@@ -42,17 +280,61 @@ impl MusicPlayer {
 }
 
 impl eframe::App for MusicPlayer {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let view = self.player.view();
+        // The playing track's position comes from the UI's own clock, so saving
+        // never waits on the audio thread.
+        let mut resume_points = ResumePoints::clone(&view.resume_points);
+        if matches!(view.state, MusicState::Playing | MusicState::Paused)
+            && let Some(path) = &view.current_path
+        {
+            resume_points.remember(path, self.pos, self.total_duration);
+        }
+        eframe::set_value(storage, RESUME_POINTS_KEY, &resume_points);
+        eframe::set_value(storage, VOLUME_KEY, &view.volume);
+        eframe::set_value(storage, EQUALIZER_KEY, &view.equalizer);
+        eframe::set_value(storage, PLAYBACK_MODE_KEY, &view.playback_mode);
+        eframe::set_value(storage, OUTPUT_DEVICE_KEY, &view.output_device);
+        eframe::set_value(storage, CROSSFADE_KEY, &view.crossfade);
+        eframe::set_value(storage, REPLAY_GAIN_KEY, &view.replay_gain);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.player.poll();
+        self.handle_events();
+        self.artwork_ui.update(ctx, &self.player);
+        self.handle_shortcuts(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
+            if !self.player.view().audio_available {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "🔇 Audio unavailable — playback is silent until an output device appears",
+                );
+                ui.add_space(5.0);
+            }
+
+            self.show_error(ui);
             self.music_path_entry_ui.show(ui);
 
             self.music_path_entry_ui.on_submit(
                 ctx,
-                &mut self.music_service,
+                &mut self.player,
                 &mut self.cover_texture,
             );
+            if let Some((path, metadata)) = self.music_path_entry_ui.edit_request.take() {
+                self.tag_editor_ui.open(path, &metadata);
+            }
+            if let Some(tracks) = self.music_path_entry_ui.batch_request.take() {
+                self.batch_editor_ui.open(tracks);
+            }
+
+            ui.add_space(5.0);
+            self.settings_ui.show(ui, &mut self.player);
+            self.equalizer_ui
+                .show(ui, &mut self.player, &self.music_button_ui);
 
-            if !self.music_service.is_music_loaded() {
+            if !self.player.view().music_loaded {
                 ui.vertical_centered(|ui| {
                     ui.add_space(20.0);
                     ui.weak("Select a song to start the player...");
@@ -60,7 +342,15 @@ impl eframe::App for MusicPlayer {
             }
         });
 
-        if self.music_service.is_music_loaded() {
+        if let Some((path, tags)) = self.tag_editor_ui.show(ctx, &mut self.player) {
+            self.music_path_entry_ui.update_metadata(&path, tags.metadata);
+        }
+        for (path, tags) in self.batch_editor_ui.show(ctx, &mut self.player) {
+            self.music_path_entry_ui.update_metadata(&path, tags.metadata);
+        }
+        self.artwork_ui.show_viewer(ctx);
+
+        if self.player.view().music_loaded {
             egui::TopBottomPanel::bottom("playback_bar")
                 .resizable(false)
                 .frame(
@@ -70,68 +360,151 @@ impl eframe::App for MusicPlayer {
                 )
                 .show(ctx, |ui| {
                     ui.vertical_centered(|ui| {
+                        self.artwork_ui.show_thumbnail(ui);
+
                         // Display Song Name
-                        ui.label(
+                        let title = ui.label(
                             egui::RichText::new(format!(
                                 "🎵 {}",
-                                self.music_service.music_file.name()
+                                self.player.view().track_name
                             ))
                             .strong()
                             .color(ctx.style().visuals.widgets.active.fg_stroke.color),
                         );
-
-                        // Timeline Logic
-                        if self.total_duration.is_none() {
-                            if let Some(duration) = self.music_service.get_total_duration() {
-                                self.total_duration = Some(duration.as_secs());
+                        title.context_menu(|ui| {
+                            if ui.button("✏ Edit tags…").clicked()
+                                && let Some(path) = self.player.view().current_path.clone()
+                            {
+                                self.tag_editor_ui.open(path, &self.player.view().metadata);
+                                ui.close();
                             }
+                        });
+                        self.show_track_details(ui);
+
+                        if let Some(index) = self.player.view().current_index {
+                            ui.weak(format!(
+                                "Track {} of {}",
+                                index + 1,
+                                self.player.view().queue_len
+                            ));
                         }
 
-                        let total = self.total_duration.unwrap_or(0);
+                        self.show_resume_offer(ui);
+
+                        // Timeline Logic
+                        let total = self.total_duration.unwrap_or_default();
 
                         // Capture slider response to handle dragging
+                        let mut ab_loop = self.player.view().ab_loop;
                         let slider_res = self.music_button_ui.timeline_slider_with_time(
                             ui,
                             &mut self.pos,
                             total,
+                            &mut ab_loop,
                         );
+                        if ab_loop != self.player.view().ab_loop {
+                            self.player.set_ab_loop(ab_loop);
+                        }
 
+                        let slider_res = slider_res
+                            .on_hover_text("← → skip 5 s · Shift 1 s · Ctrl 30 s");
+                        self.seeking = slider_res.dragged();
                         if slider_res.dragged() || slider_res.clicked() {
                             // Update the actual playback position while dragging
-                            let _ = self
-                                .music_service
-                                .set_pos(std::time::Duration::from_secs(self.pos));
-                        } else {
-                            // Sync the slider position with the music service while NOT dragging
-                            if let Some(current_pos) = self.music_service.get_pos() {
-                                self.pos = current_pos.as_secs();
-                            }
+                            self.player.set_pos(self.pos);
                         }
 
 
                         // Control Buttons 
                         ui.vertical_centered(|ui| {
                             ui.horizontal(|ui| {
-                                let total_buttons_width =
-                                    (60.0 * 3.0) + (ui.spacing().item_spacing.x * 2.0);
+                                let spacing = ui.spacing().item_spacing.x;
+                                // Five transport buttons plus the shuffle and repeat toggles.
+                                let total_buttons_width = (60.0 * 5.0) + (30.0 * 2.0) + (spacing * 6.0);
                                 let padding = (ui.available_width() - total_buttons_width) / 2.0;
 
-                                if padding > 0.0 {
-                                    ui.add_space(padding);
+                                // A-B loop controls sit on the left, inside the centering padding.
+                                let loop_controls_width = (28.0 * 3.0) + (spacing * 3.0);
+                                self.show_loop_controls(ui);
+
+                                if padding - loop_controls_width > 0.0 {
+                                    ui.add_space(padding - loop_controls_width);
+                                }
+
+                                let mode = self.player.view().playback_mode;
+                                if self
+                                    .music_button_ui
+                                    .show_shuffle_button(ui, mode.shuffle)
+                                    .clicked()
+                                {
+                                    self.player.toggle_shuffle();
+                                }
+
+                                if self.music_button_ui.show_previous_button(ui).clicked() {
+                                    self.player.play_previous();
                                 }
 
                                 if self.music_button_ui.show_pause_button(ui).clicked() {
-                                    self.music_service.pause();
+                                    self.player.pause();
                                 }
                                 
                                 if self.music_button_ui.show_play_button(ui).clicked() {
-                                    self.music_service.resume();
+                                    self.player.resume();
                                 }
 
 
                                 if self.music_button_ui.show_stop_button(ui).clicked() {
-                                    self.music_service.stop();
+                                    self.player.stop();
+                                }
+
+                                if self.music_button_ui.show_next_button(ui).clicked() {
+                                    self.player.play_next();
                                 }
+
+                                if self
+                                    .music_button_ui
+                                    .show_repeat_button(ui, mode.repeat)
+                                    .clicked()
+                                {
+                                    self.player.cycle_repeat();
+                                }
+
+                                // Volume sits on the right edge, outside the centered group.
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        let volume = self.player.view().volume;
+                                        let mut level = volume.level();
+                                        if self
+                                            .music_button_ui
+                                            .volume_slider(ui, &mut level, volume.is_muted())
+                                            .changed()
+                                        {
+                                            self.player.set_volume(level);
+                                        }
+                                        if self
+                                            .music_button_ui
+                                            .show_mute_button(ui, volume.is_muted(), level)
+                                            .clicked()
+                                        {
+                                            self.player.toggle_mute();
+                                        }
+
+                                        let mut speed = self.player.view().speed;
+                                        if self
+                                            .music_button_ui
+                                            .show_speed_selector(ui, &mut speed)
+                                        {
+                                            self.player.set_speed(speed);
+                                        }
+
+                                        self.sleep_timer_ui.show(
+                                            ui,
+                                            &mut self.player,
+                                            &self.music_button_ui,
+                                        );
+                                    },
+                                );
                             });
                         });
                     });
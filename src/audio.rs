@@ -0,0 +1,7 @@
+pub mod backend;
+pub mod equalizer;
+pub mod fade;
+pub mod gain;
+pub mod stretch;
+pub mod transport;
+pub mod wav;
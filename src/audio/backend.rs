@@ -0,0 +1,159 @@
+use std::{
+    io,
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rodio::{
+    ChannelCount, OutputStream, SampleRate,
+    mixer::{Mixer, MixerSource},
+};
+
+use crate::audio::wav;
+
+/// Where the service's sinks end up: anything that owns a mixer and drains it.
+pub trait OutputBackend {
+    fn mixer(&self) -> &Mixer;
+
+    /// False for backends nobody can hear, such as the silent fallback.
+    fn is_audible(&self) -> bool {
+        true
+    }
+}
+
+impl OutputBackend for OutputStream {
+    fn mixer(&self) -> &Mixer {
+        OutputStream::mixer(self)
+    }
+}
+
+/// Drains its mixer at real-time speed and throws the samples away.
+///
+/// Used when no audio device can be opened: sinks still advance, so positions,
+/// track ends and the queue behave as if something were playing.
+pub struct NullBackend {
+    mixer: Mixer,
+    running: Arc<AtomicBool>,
+    clock: Option<JoinHandle<()>>,
+}
+
+impl Default for NullBackend {
+    fn default() -> Self {
+        Self::new(2, 44_100)
+    }
+}
+
+impl NullBackend {
+    const TICK: Duration = Duration::from_millis(10);
+
+    pub fn new(channels: ChannelCount, sample_rate: SampleRate) -> Self {
+        let (mixer, mut source) = rodio::mixer::mixer(channels, sample_rate);
+        let running = Arc::new(AtomicBool::new(true));
+
+        let clock = {
+            let running = running.clone();
+            thread::spawn(move || {
+                let start = Instant::now();
+                let mut frames_done = 0u64;
+                while running.load(Ordering::Relaxed) {
+                    // Catch up to the wall clock instead of counting ticks, so it never drifts.
+                    let due = (start.elapsed().as_secs_f64() * sample_rate as f64) as u64;
+                    for _ in frames_done..due {
+                        for _ in 0..channels {
+                            source.next();
+                        }
+                    }
+                    frames_done = due;
+                    thread::sleep(Self::TICK);
+                }
+            })
+        };
+
+        Self {
+            mixer,
+            running,
+            clock: Some(clock),
+        }
+    }
+}
+
+impl OutputBackend for NullBackend {
+    fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+
+    fn is_audible(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for NullBackend {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(clock) = self.clock.take() {
+            let _ = clock.join();
+        }
+    }
+}
+
+/// Renders on demand into memory instead of playing, as fast as the caller asks.
+///
+/// Clones share the same mixer, so one can be handed to `MusicService` while the
+/// other pulls the output. Nothing plays between calls to [`OfflineBackend::render`].
+#[derive(Clone)]
+pub struct OfflineBackend {
+    mixer: Mixer,
+    source: Arc<Mutex<MixerSource>>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+impl OfflineBackend {
+    pub fn new(channels: ChannelCount, sample_rate: SampleRate) -> Self {
+        let (mixer, source) = rodio::mixer::mixer(channels, sample_rate);
+        Self {
+            mixer,
+            source: Arc::new(Mutex::new(source)),
+            channels,
+            sample_rate,
+        }
+    }
+
+    pub fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    /// Pulls `frames` frames of interleaved output; silence when nothing is playing.
+    pub fn render_frames(&self, frames: usize) -> Vec<f32> {
+        let mut source = self.source.lock().expect("offline mixer poisoned");
+        (0..frames * self.channels as usize)
+            .map(|_| source.next().unwrap_or(0.0))
+            .collect()
+    }
+
+    pub fn render(&self, duration: Duration) -> Vec<f32> {
+        let frames = (duration.as_secs_f64() * self.sample_rate as f64).round() as usize;
+        self.render_frames(frames)
+    }
+
+    /// Renders `duration` of output straight into a WAV file.
+    pub fn render_to_wav(&self, path: impl AsRef<Path>, duration: Duration) -> io::Result<()> {
+        let samples = self.render(duration);
+        wav::write(path, self.channels, self.sample_rate, &samples)
+    }
+}
+
+impl OutputBackend for OfflineBackend {
+    fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+}
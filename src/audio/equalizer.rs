@@ -0,0 +1,328 @@
+use std::{
+    f32::consts::PI,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use serde::{Deserialize, Serialize};
+
+/// Centre frequencies of the ten octave bands of the graphic equalizer.
+pub const BAND_FREQUENCIES: [f32; 10] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+
+pub const GAIN_RANGE_DB: std::ops::RangeInclusive<f32> = -12.0..=12.0;
+
+/// One peaking filter. The graphic equalizer only changes `gain_db`, but frequency
+/// and Q are kept per band so the same chain works as a parametric equalizer.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EqBand {
+    pub frequency: f32,
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EqParams {
+    pub enabled: bool,
+    pub bands: Vec<EqBand>,
+}
+
+impl Default for EqParams {
+    fn default() -> Self {
+        // Q of an octave-wide band.
+        const OCTAVE_Q: f32 = std::f32::consts::SQRT_2;
+        Self {
+            enabled: false,
+            bands: BAND_FREQUENCIES
+                .iter()
+                .map(|&frequency| EqBand {
+                    frequency,
+                    gain_db: 0.0,
+                    q: OCTAVE_Q,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl EqParams {
+    /// Attenuation applied before the bands so the loudest boost cannot clip.
+    fn headroom(&self) -> f32 {
+        let max_boost = self
+            .bands
+            .iter()
+            .map(|band| band.gain_db)
+            .fold(0.0f32, f32::max);
+        10f32.powf(-max_boost / 20.0)
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    params: Mutex<EqParams>,
+    version: AtomicU64,
+}
+
+/// Shared equalizer settings; every [`Equalizer`] source built from it follows changes live.
+#[derive(Clone, Debug)]
+pub struct EqualizerHandle {
+    shared: Arc<Shared>,
+}
+
+impl EqualizerHandle {
+    pub fn new(params: EqParams) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                params: Mutex::new(params),
+                version: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn params(&self) -> EqParams {
+        self.shared.params.lock().unwrap().clone()
+    }
+
+    pub fn set_params(&self, params: EqParams) {
+        *self.shared.params.lock().unwrap() = params;
+        self.shared.version.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn apply<S: Source>(&self, input: S) -> Equalizer<S> {
+        let mut equalizer = Equalizer {
+            input,
+            shared: self.shared.clone(),
+            version: u64::MAX,
+            filters: Vec::new(),
+            bypass: true,
+            headroom: 1.0,
+            channel: 0,
+            until_poll: 0,
+        };
+        equalizer.reload();
+        equalizer
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Biquad {
+    /// Peaking filter from the RBJ "Audio EQ Cookbook".
+    fn peaking(band: &EqBand, sample_rate: f32) -> Self {
+        let a = 10f32.powf(band.gain_db / 40.0);
+        let w0 = 2.0 * PI * band.frequency / sample_rate;
+        let alpha = w0.sin() / (2.0 * band.q);
+        let cos_w0 = w0.cos();
+
+        let a0 = 1.0 + alpha / a;
+        Self {
+            b0: (1.0 + alpha * a) / a0,
+            b1: (-2.0 * cos_w0) / a0,
+            b2: (1.0 - alpha * a) / a0,
+            a1: (-2.0 * cos_w0) / a0,
+            a2: (1.0 - alpha / a) / a0,
+        }
+    }
+}
+
+/// A biquad with its own delay line for every channel (transposed direct form II).
+/// Flat bands and bands above Nyquist stay in the chain but are skipped.
+struct Filter {
+    coefficients: Biquad,
+    active: bool,
+    state: Vec<[f32; 2]>,
+}
+
+impl Filter {
+    #[inline]
+    fn process(&mut self, channel: usize, x: f32) -> f32 {
+        let c = &self.coefficients;
+        let z = &mut self.state[channel];
+        let y = c.b0 * x + z[0];
+        z[0] = c.b1 * x - c.a1 * y + z[1];
+        z[1] = c.b2 * x - c.a2 * y;
+        y
+    }
+}
+
+/// Runs the decoded signal through the bands of an [`EqualizerHandle`].
+pub struct Equalizer<S> {
+    input: S,
+    shared: Arc<Shared>,
+    version: u64,
+    /// One per band, in band order.
+    filters: Vec<Filter>,
+    /// No band is active.
+    bypass: bool,
+    headroom: f32,
+    channel: usize,
+    until_poll: u32,
+}
+
+impl<S: Source> Equalizer<S> {
+    /// Samples between checks for new settings (about 5 ms at 48 kHz stereo).
+    const POLL_INTERVAL: u32 = 512;
+
+    fn reload(&mut self) {
+        let version = self.shared.version.load(Ordering::Acquire);
+        if version == self.version {
+            return;
+        }
+        self.version = version;
+
+        let params = self.shared.params.lock().unwrap().clone();
+        let sample_rate = self.input.sample_rate() as f32;
+        let channels = self.input.channels().max(1) as usize;
+
+        // Keep each band's delay line so live tweaks don't click. A band that
+        // was skipped has a stale one and starts from silence.
+        let mut old = std::mem::take(&mut self.filters).into_iter();
+        self.filters = params
+            .bands
+            .iter()
+            .map(|band| {
+                let state = old
+                    .next()
+                    .filter(|f| f.active && f.state.len() == channels)
+                    .map_or_else(|| vec![[0.0; 2]; channels], |f| f.state);
+                Filter {
+                    coefficients: Biquad::peaking(band, sample_rate),
+                    active: params.enabled
+                        && band.gain_db != 0.0
+                        && band.frequency < sample_rate / 2.0,
+                    state,
+                }
+            })
+            .collect();
+        self.bypass = !self.filters.iter().any(|filter| filter.active);
+        self.headroom = if params.enabled { params.headroom() } else { 1.0 };
+    }
+}
+
+impl<S: Source> Iterator for Equalizer<S> {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        // Only swap coefficients on a frame boundary so channels stay in step.
+        if self.channel == 0 {
+            if self.until_poll == 0 {
+                self.reload();
+                self.until_poll = Self::POLL_INTERVAL;
+            }
+            self.until_poll -= 1;
+        }
+
+        let sample = self.input.next()?;
+        let channel = self.channel;
+        self.channel = (self.channel + 1) % self.input.channels().max(1) as usize;
+
+        if self.bypass {
+            return Some(sample);
+        }
+        let mut value = sample * self.headroom;
+        for filter in &mut self.filters {
+            if filter.active && channel < filter.state.len() {
+                value = filter.process(channel, value);
+            }
+        }
+        Some(value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<S: Source> Source for Equalizer<S> {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        // The old delay lines belong to a different part of the signal.
+        for filter in &mut self.filters {
+            filter.state.iter_mut().for_each(|z| *z = [0.0; 2]);
+        }
+        self.channel = 0;
+        self.input.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rodio::source::SineWave;
+
+    use super::*;
+
+    fn boosted(bands: &[usize]) -> EqParams {
+        let mut params = EqParams {
+            enabled: true,
+            ..EqParams::default()
+        };
+        for &band in bands {
+            params.bands[band].gain_db = 6.0;
+        }
+        params
+    }
+
+    fn delay_lines<S: Source>(equalizer: &Equalizer<S>) -> Vec<Vec<[f32; 2]>> {
+        equalizer.filters.iter().map(|f| f.state.clone()).collect()
+    }
+
+    #[test]
+    fn retuning_one_band_keeps_the_others_delay_lines() {
+        let handle = EqualizerHandle::new(boosted(&[3, 7]));
+        let mut equalizer = handle.apply(SineWave::new(440.0));
+        equalizer.by_ref().take(1000).for_each(drop);
+        let before = delay_lines(&equalizer);
+        assert_ne!(before[7], [[0.0; 2]]);
+
+        // Turning up a flat band between the two must not shift their state.
+        handle.set_params(boosted(&[3, 5, 7]));
+        equalizer.reload();
+        let after = delay_lines(&equalizer);
+        assert_eq!(after[3], before[3]);
+        assert_eq!(after[5], [[0.0; 2]]);
+        assert_eq!(after[7], before[7]);
+
+        // Nor flattening it again.
+        equalizer.by_ref().take(1000).for_each(drop);
+        let before = delay_lines(&equalizer);
+        handle.set_params(boosted(&[3, 7]));
+        equalizer.reload();
+        let after = delay_lines(&equalizer);
+        assert_eq!(after[3], before[3]);
+        assert_eq!(after[7], before[7]);
+    }
+}
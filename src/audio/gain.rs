@@ -0,0 +1,81 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+
+/// Handle for changing a [`Gain`] factor while the source is playing.
+#[derive(Clone, Debug)]
+pub struct GainHandle {
+    factor: Arc<AtomicU32>,
+}
+
+impl GainHandle {
+    pub fn set(&self, factor: f32) {
+        self.factor.store(factor.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.factor.load(Ordering::Relaxed))
+    }
+}
+
+/// Multiplies every sample by a factor that can be changed from another thread.
+pub struct Gain<S> {
+    input: S,
+    factor: Arc<AtomicU32>,
+}
+
+pub fn gain<S: Source>(input: S, factor: f32) -> (Gain<S>, GainHandle) {
+    let factor = Arc::new(AtomicU32::new(factor.to_bits()));
+    let handle = GainHandle {
+        factor: factor.clone(),
+    };
+    (Gain { input, factor }, handle)
+}
+
+impl<S: Source> Iterator for Gain<S> {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        let factor = f32::from_bits(self.factor.load(Ordering::Relaxed));
+        self.input.next().map(|sample| sample * factor)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<S: Source> Source for Gain<S> {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
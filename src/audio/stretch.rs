@@ -0,0 +1,340 @@
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+
+pub const SPEED_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
+/// Playback speed shared by every [`TimeStretch`] source.
+#[derive(Clone, Debug)]
+pub struct SpeedHandle {
+    speed: Arc<AtomicU32>,
+}
+
+impl Default for SpeedHandle {
+    fn default() -> Self {
+        Self {
+            speed: Arc::new(AtomicU32::new(1.0f32.to_bits())),
+        }
+    }
+}
+
+impl SpeedHandle {
+    pub fn set(&self, speed: f32) {
+        let speed = speed.clamp(*SPEED_RANGE.start(), *SPEED_RANGE.end());
+        self.speed.store(speed.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.speed.load(Ordering::Relaxed))
+    }
+
+    pub fn apply<S: Source>(&self, input: S) -> (TimeStretch<S>, PositionHandle) {
+        let position = PositionHandle::default();
+        let stretch = TimeStretch {
+            input,
+            speed: self.clone(),
+            position: position.clone(),
+            source_frames: 0.0,
+            offset: Duration::ZERO,
+            wsola: None,
+            channel: 0,
+        };
+        (stretch, position)
+    }
+}
+
+/// Position of a [`TimeStretch`] source in the *recording's* time, whatever the speed.
+#[derive(Clone, Debug, Default)]
+pub struct PositionHandle {
+    micros: Arc<AtomicU64>,
+}
+
+impl PositionHandle {
+    pub fn get(&self) -> Duration {
+        Duration::from_micros(self.micros.load(Ordering::Relaxed))
+    }
+
+    /// Overwritten by the source on its next frame; lets a requested seek show up
+    /// right away, even while paused.
+    pub fn set(&self, pos: Duration) {
+        self.micros.store(pos.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Changes tempo without changing pitch (WSOLA: waveform-similarity overlap-add).
+///
+/// At 1× the input is passed through untouched. Once the speed changes, windows of
+/// the input are taken at `speed` times the output hop, nudged to where they best
+/// line up with the previous window, and overlap-added with a Hann window.
+pub struct TimeStretch<S> {
+    input: S,
+    speed: SpeedHandle,
+    position: PositionHandle,
+    /// Input frames represented by the output so far, since `offset`.
+    source_frames: f64,
+    offset: Duration,
+    wsola: Option<Wsola>,
+    channel: usize,
+}
+
+impl<S: Source> TimeStretch<S> {
+    fn channel_count(&self) -> usize {
+        self.input.channels().max(1) as usize
+    }
+
+    fn publish_position(&self) {
+        let secs = self.source_frames / self.input.sample_rate().max(1) as f64;
+        self.position
+            .set(self.offset + Duration::from_secs_f64(secs.max(0.0)));
+    }
+}
+
+impl<S: Source> Iterator for TimeStretch<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let speed = self.speed.get();
+
+        // Only switch modes on a frame boundary so channels stay in step.
+        if self.channel == 0 && self.wsola.is_none() && speed != 1.0 {
+            let sample_rate = self.input.sample_rate();
+            let channels = self.channel_count();
+            self.wsola = Some(Wsola::new(&mut self.input, sample_rate, channels));
+        }
+
+        let sample = match &mut self.wsola {
+            None => self.input.next()?,
+            Some(wsola) => wsola.next_sample(&mut self.input, speed)?,
+        };
+
+        self.channel += 1;
+        if self.channel == self.channel_count() {
+            self.channel = 0;
+            self.source_frames += speed as f64;
+            // Cheap enough per frame, and keeps the timeline exact.
+            self.publish_position();
+        }
+        Some(sample)
+    }
+}
+
+impl<S: Source> Source for TimeStretch<S> {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        match self.wsola {
+            None => self.input.current_span_len(),
+            Some(_) => None,
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.wsola = None;
+        self.channel = 0;
+        self.offset = pos;
+        self.source_frames = 0.0;
+        self.publish_position();
+        Ok(())
+    }
+}
+
+struct Wsola {
+    channels: usize,
+    /// Window length in frames.
+    window: usize,
+    /// Output hop in frames (half a window, so Hann windows sum to one).
+    hop: usize,
+    /// How far a window may move to line up with the previous one, in frames.
+    tolerance: usize,
+    hann: Vec<f32>,
+    /// Interleaved input, starting at absolute frame `input_start`.
+    input: VecDeque<f32>,
+    input_start: usize,
+    input_ended: bool,
+    /// Absolute input frame where the previous window started.
+    prev_pos: isize,
+    /// Ideal (unaligned) start of the next window.
+    nominal: f64,
+    /// Overlap-add accumulator, one window long.
+    overlap: Vec<f32>,
+    ready: VecDeque<f32>,
+}
+
+impl Wsola {
+    fn new<S: Source>(source: &mut S, sample_rate: SampleRate, channels: usize) -> Self {
+        let window = ((sample_rate as usize * 40) / 1000).max(64) & !1;
+        let hop = window / 2;
+        let hann = (0..window)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / window as f32).cos())
+            .collect();
+
+        let mut wsola = Self {
+            channels,
+            window,
+            hop,
+            tolerance: (sample_rate as usize * 10) / 1000,
+            hann,
+            input: VecDeque::new(),
+            input_start: 0,
+            input_ended: false,
+            prev_pos: -(hop as isize),
+            nominal: 0.0,
+            overlap: vec![0.0; window * channels],
+            ready: VecDeque::new(),
+        };
+
+        // Pretend a window started half a window ago: its falling half covers the
+        // frames about to play, so the first real window blends in seamlessly.
+        wsola.fill(source, hop);
+        for frame in 0..hop.min(wsola.buffered_frames()) {
+            let w = wsola.hann[hop + frame];
+            for ch in 0..channels {
+                wsola.overlap[frame * channels + ch] = wsola.input[frame * channels + ch] * w;
+            }
+        }
+        wsola.nominal = -(hop as f64);
+        wsola
+    }
+
+    fn buffered_frames(&self) -> usize {
+        self.input.len() / self.channels
+    }
+
+    /// Reads until at least `frames` frames are buffered or the input ends.
+    fn fill<S: Source>(&mut self, source: &mut S, frames: usize) {
+        while !self.input_ended && self.buffered_frames() < frames {
+            match source.next() {
+                Some(sample) => self.input.push_back(sample),
+                None => self.input_ended = true,
+            }
+        }
+        // Drop a trailing partial frame at end of stream.
+        if self.input_ended {
+            let whole = self.buffered_frames() * self.channels;
+            self.input.truncate(whole);
+        }
+    }
+
+    fn next_sample<S: Source>(&mut self, source: &mut S, speed: f32) -> Option<f32> {
+        if self.ready.is_empty() {
+            self.step(source, speed);
+        }
+        self.ready.pop_front()
+    }
+
+    /// Adds one window to the output and releases one hop of finished samples.
+    fn step<S: Source>(&mut self, source: &mut S, speed: f32) {
+        self.nominal += self.hop as f64 * speed as f64;
+        let natural = self.prev_pos + self.hop as isize;
+        let target = self.nominal.round() as isize;
+
+        let needed_end = (target + (self.tolerance + self.window) as isize)
+            .max(natural + self.window as isize);
+        let needed = (needed_end - self.input_start as isize).max(0) as usize;
+        self.fill(source, needed);
+
+        let available_end = (self.input_start + self.buffered_frames()) as isize;
+        let latest_start = available_end - self.window as isize;
+        if target > latest_start || latest_start < self.input_start as isize {
+            // End of stream: release the last window's tail and stop.
+            let tail = (self.hop * self.channels).min(self.overlap.len());
+            self.ready.extend(self.overlap.drain(..tail));
+            self.overlap.clear();
+            return;
+        }
+
+        let chosen = self.best_alignment(natural, target, latest_start);
+        let start = (chosen - self.input_start as isize) as usize;
+        for frame in 0..self.window {
+            let w = self.hann[frame];
+            for ch in 0..self.channels {
+                let i = frame * self.channels + ch;
+                let x = self.input.get(start * self.channels + i).copied().unwrap_or(0.0);
+                self.overlap[i] += x * w;
+            }
+        }
+
+        let hop_samples = self.hop * self.channels;
+        self.ready.extend(self.overlap.drain(..hop_samples));
+        self.overlap.resize(self.window * self.channels, 0.0);
+        self.prev_pos = chosen;
+
+        // Forget input that no future window can reach.
+        let keep_from = (self.nominal as isize - self.tolerance as isize)
+            .min(self.prev_pos + self.hop as isize)
+            .max(self.input_start as isize) as usize;
+        let drop_frames = (keep_from - self.input_start).min(self.buffered_frames());
+        self.input.drain(..drop_frames * self.channels);
+        self.input_start += drop_frames;
+    }
+
+    /// Finds the window start near `target` that best continues the window at `natural`.
+    fn best_alignment(&self, natural: isize, target: isize, latest_start: isize) -> isize {
+        let lowest = (target - self.tolerance as isize).max(self.input_start as isize);
+        let highest = (target + self.tolerance as isize).min(latest_start);
+        if highest < lowest || natural < self.input_start as isize {
+            return target.max(self.input_start as isize).min(latest_start);
+        }
+
+        // Coarse search on every 4th candidate over the overlapping half, then refine.
+        let mut best = (lowest, f32::MIN);
+        let mut candidate = lowest;
+        while candidate <= highest {
+            let score = self.similarity(natural, candidate, 4);
+            if score > best.1 {
+                best = (candidate, score);
+            }
+            candidate += 4;
+        }
+        let coarse = best.0;
+        for candidate in (coarse - 3).max(lowest)..=(coarse + 3).min(highest) {
+            let score = self.similarity(natural, candidate, 1);
+            if score > best.1 {
+                best = (candidate, score);
+            }
+        }
+        best.0
+    }
+
+    /// Normalised cross-correlation of the mono mix of two windows' first halves.
+    fn similarity(&self, a: isize, b: isize, stride: usize) -> f32 {
+        let frame = |pos: isize, offset: usize| -> f32 {
+            let base = (pos as usize - self.input_start + offset) * self.channels;
+            (0..self.channels)
+                .map(|ch| self.input.get(base + ch).copied().unwrap_or(0.0))
+                .sum()
+        };
+
+        let (mut dot, mut energy) = (0.0f32, 0.0f32);
+        for offset in (0..self.hop).step_by(stride) {
+            let x = frame(a, offset);
+            let y = frame(b, offset);
+            dot += x * y;
+            energy += y * y;
+        }
+        dot / (energy.sqrt() + 1e-6)
+    }
+}
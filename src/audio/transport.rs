@@ -0,0 +1,201 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+
+const NO_SEEK: u64 = u64::MAX;
+
+/// Default length of the ramps around pause, resume, stop and seek.
+pub const DEFAULT_RAMP: Duration = Duration::from_millis(10);
+pub const MAX_RAMP: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct Shared {
+    paused: AtomicBool,
+    stopped: AtomicBool,
+    pending_seek_micros: AtomicU64,
+    ramp_micros: AtomicU64,
+}
+
+/// Pauses, stops and seeks a [`Transport`] source without waiting for the audio thread.
+///
+/// `Sink::try_seek` blocks until the output pulls another sample, which never
+/// happens for a paused sink or an offline renderer that is not being driven, and
+/// `Sink::pause`/`stop` cut the waveform wherever it happens to be.
+#[derive(Clone, Debug)]
+pub struct TransportHandle {
+    shared: Arc<Shared>,
+}
+
+impl TransportHandle {
+    pub fn pause(&self) {
+        self.shared.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn play(&self) {
+        self.shared.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Fades out and ends the source for good.
+    pub fn stop(&self) {
+        self.shared.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn seek(&self, pos: Duration) {
+        let micros = (pos.as_micros() as u64).min(NO_SEEK - 1);
+        self.shared
+            .pending_seek_micros
+            .store(micros, Ordering::Relaxed);
+    }
+
+    /// Target of a seek still waiting for its fade-out to finish.
+    pub fn pending_seek(&self) -> Option<Duration> {
+        match self.shared.pending_seek_micros.load(Ordering::Relaxed) {
+            NO_SEEK => None,
+            micros => Some(Duration::from_micros(micros)),
+        }
+    }
+
+    pub fn set_ramp(&self, ramp: Duration) {
+        self.shared
+            .ramp_micros
+            .store(ramp.min(MAX_RAMP).as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Outermost stage of a track's chain. Ramps the gain down before pausing, stopping
+/// or seeking and back up afterwards; while paused it outputs silence without
+/// pulling from `input`, so the position holds.
+pub struct Transport<S> {
+    input: S,
+    shared: Arc<Shared>,
+    gain: f32,
+    started: bool,
+    /// Set per frame: paused and silent, so `input` is left alone.
+    holding: bool,
+    channel: ChannelCount,
+}
+
+pub fn transport<S: Source>(input: S, ramp: Duration) -> (Transport<S>, TransportHandle) {
+    let handle = TransportHandle {
+        shared: Arc::new(Shared {
+            paused: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            pending_seek_micros: AtomicU64::new(NO_SEEK),
+            ramp_micros: AtomicU64::new(0),
+        }),
+    };
+    handle.set_ramp(ramp);
+    let transport = Transport {
+        input,
+        shared: handle.shared.clone(),
+        gain: 1.0,
+        started: false,
+        holding: false,
+        channel: 0,
+    };
+    (transport, handle)
+}
+
+impl<S: Source> Transport<S> {
+    /// Gain change per frame for the configured ramp length.
+    fn step(&self) -> f32 {
+        let micros = self.shared.ramp_micros.load(Ordering::Relaxed);
+        let frames = micros as f32 * self.input.sample_rate() as f32 / 1_000_000.0;
+        if frames < 1.0 { 1.0 } else { 1.0 / frames }
+    }
+
+    /// Moves the gain one frame towards where the controls want it. Returns false
+    /// once the source should end.
+    fn next_frame(&mut self) -> bool {
+        let stopped = self.shared.stopped.load(Ordering::Relaxed);
+        let paused = self.shared.paused.load(Ordering::Relaxed);
+        let seeking = self.shared.pending_seek_micros.load(Ordering::Relaxed) != NO_SEEK;
+
+        // Nothing audible yet means nothing to fade out.
+        if !self.started && (stopped || paused) {
+            self.gain = 0.0;
+        }
+        if stopped && self.gain == 0.0 {
+            return false;
+        }
+        if seeking && (!self.started || self.gain == 0.0) {
+            let micros = self
+                .shared
+                .pending_seek_micros
+                .swap(NO_SEEK, Ordering::Relaxed);
+            let pos = Duration::from_micros(micros);
+            if micros != NO_SEEK
+                && let Err(e) = self.input.try_seek(pos)
+            {
+                eprintln!("Error seeking to {:?}: {:?}", pos, e);
+            }
+        }
+
+        let target = if stopped || paused || seeking { 0.0 } else { 1.0 };
+        let step = self.step();
+        self.gain = if target > self.gain {
+            (self.gain + step).min(target)
+        } else {
+            (self.gain - step).max(target)
+        };
+        self.holding = paused && self.gain == 0.0;
+        true
+    }
+}
+
+impl<S: Source> Iterator for Transport<S> {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.channel == 0 && !self.next_frame() {
+            return None;
+        }
+        self.channel = (self.channel + 1) % self.input.channels().max(1);
+
+        // Fully faded out and paused: hold the position with silence.
+        if self.holding {
+            return Some(0.0);
+        }
+        let sample = self.input.next()?;
+        self.started = true;
+        Some(sample * self.gain)
+    }
+}
+
+impl<S: Source> Source for Transport<S> {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.shared
+            .pending_seek_micros
+            .store(NO_SEEK, Ordering::Relaxed);
+        self.channel = 0;
+        self.holding = false;
+        self.input.try_seek(pos)
+    }
+}
@@ -0,0 +1,7 @@
+pub mod app;
+pub mod audio;
+pub mod components;
+pub mod models;
+pub mod services;
+pub mod ui;
+pub mod utils;
@@ -7,14 +7,7 @@
 
 use eframe::{self, egui};
 
-pub mod app;
-pub mod models;
-pub mod services;
-pub mod ui;
-pub mod components;
-pub mod utils;
-
-use crate::app::MusicPlayer;
+use music_player::app::MusicPlayer;
 
 fn main() {
     let screen_size = (600.0, 400.0);
@@ -1 +1,5 @@
+pub mod artwork;
+pub mod metadata;
+pub mod replay_gain;
+pub mod tags;
 pub mod track;
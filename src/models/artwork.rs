@@ -0,0 +1,92 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::models::tags::picture::{Picture, PictureKind};
+
+const IMAGE_EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "gif", "bmp", "webp"];
+/// File names (without extension) that mean "the front cover", best first.
+const COVER_NAMES: [&str; 5] = ["cover", "folder", "front", "album", "albumart"];
+/// Subfolders rips keep back covers, scans and booklets in.
+const ARTWORK_DIRS: [&str; 5] = ["scans", "artwork", "covers", "booklet", "images"];
+
+/// Image files next to `track` and in its artwork subfolders. Front covers by
+/// name come first, then everything else by path.
+pub fn sidecar_images(track: &Path) -> Vec<PathBuf> {
+    let Some(dir) = track.parent() else {
+        return Vec::new();
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+
+    let mut images = image_files(dir);
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() && ARTWORK_DIRS.contains(&lowercase_name(&path).as_str()) {
+            images.extend(image_files(&path));
+        }
+    }
+
+    images.sort_by_cached_key(|path| {
+        let rank = cover_rank(path).unwrap_or(COVER_NAMES.len());
+        (rank, path.parent() != Some(dir), path.clone())
+    });
+    images
+}
+
+/// Where `path` ranks among the front-cover names, if it is one.
+pub fn cover_rank(path: &Path) -> Option<usize> {
+    let stem = path.file_stem()?.to_string_lossy().to_lowercase();
+    COVER_NAMES.iter().position(|name| *name == stem)
+}
+
+/// Reads an image file as a picture, guessing what it shows from its name.
+pub fn read_sidecar(path: &Path) -> io::Result<Picture> {
+    let name = lowercase_name(path);
+    let in_booklet = path.parent().map(lowercase_name).as_deref() == Some("booklet");
+    let kind = if cover_rank(path).is_some() {
+        PictureKind::FrontCover
+    } else if name.contains("back") {
+        PictureKind::BackCover
+    } else if in_booklet || name.contains("booklet") {
+        PictureKind::Leaflet
+    } else if ["cd", "disc", "media"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+    {
+        PictureKind::Media
+    } else {
+        PictureKind::Other
+    };
+    let mut picture = Picture::new(kind, fs::read(path)?);
+    picture.description = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(picture)
+}
+
+fn image_files(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path.extension().is_some_and(|extension| {
+                    IMAGE_EXTENSIONS.contains(&extension.to_string_lossy().to_lowercase().as_str())
+                })
+        })
+        .collect()
+}
+
+fn lowercase_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
@@ -0,0 +1,207 @@
+use std::{path::Path, time::Duration};
+
+use id3::{Tag, TagLike};
+
+use crate::models::tags;
+
+/// Descriptive tags of a track. Anything the file doesn't say is `None` (or empty);
+/// display fallbacks live on [`Track`](crate::models::track::Track).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrackMetadata {
+    pub title: Option<String>,
+    pub artists: Vec<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    pub track_total: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
+    pub year: Option<i32>,
+    /// Full date as tagged, e.g. `2003-05-12`, when it says more than the year.
+    pub date: Option<String>,
+    pub genre: Option<String>,
+    pub composer: Option<String>,
+    pub comment: Option<String>,
+    pub duration: Option<Duration>,
+}
+
+impl TrackMetadata {
+    pub fn from_id3(tag: &Tag) -> Self {
+        // ID3v2.4 keeps the date in TDRC; v2.3 splits it into TYER/TDAT.
+        let recorded = tag.date_recorded().or_else(|| tag.date_released());
+        let year = tag.year().or(recorded.map(|date| date.year));
+        let date = recorded
+            .filter(|date| date.month.is_some())
+            .map(|date| date.to_string());
+
+        Self {
+            title: non_empty(tag.title()),
+            artists: tag
+                .artists()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|artist| non_empty(Some(artist)))
+                .collect(),
+            album: non_empty(tag.album()),
+            album_artist: non_empty(tag.album_artist()),
+            track_number: tag.track(),
+            track_total: tag.total_tracks(),
+            disc_number: tag.disc(),
+            disc_total: tag.total_discs(),
+            year,
+            date,
+            // Resolves ID3v1 genre numbers such as "(17)".
+            genre: non_empty(tag.genre_parsed().as_deref()),
+            composer: non_empty(tag.text_for_frame_id("TCOM")),
+            comment: tag
+                .comments()
+                .find(|comment| comment.description.is_empty())
+                .or_else(|| tag.comments().next())
+                .and_then(|comment| non_empty(Some(&comment.text))),
+            duration: tag
+                .duration()
+                .map(|millis| Duration::from_millis(u64::from(millis))),
+        }
+    }
+
+    /// Maps Vorbis comment names, which the FLAC, Ogg, MP4 and APEv2 readers all
+    /// translate to. The first value of a single-valued field wins.
+    pub fn from_comments(comments: &[(String, String)]) -> Self {
+        let mut metadata = Self::default();
+        let mut date = None;
+
+        for (key, value) in comments {
+            let Some(value) = non_empty(Some(value)) else {
+                continue;
+            };
+            let field = match key.as_str() {
+                "TITLE" => &mut metadata.title,
+                "ARTIST" => {
+                    metadata.artists.push(value);
+                    continue;
+                }
+                "ALBUM" => &mut metadata.album,
+                "ALBUMARTIST" | "ALBUM ARTIST" => &mut metadata.album_artist,
+                "DATE" | "YEAR" => &mut date,
+                "GENRE" => &mut metadata.genre,
+                "COMPOSER" => &mut metadata.composer,
+                "COMMENT" | "DESCRIPTION" => &mut metadata.comment,
+                "TRACKNUMBER" => {
+                    let (number, total) = parse_position(&value);
+                    metadata.track_number = metadata.track_number.or(number);
+                    metadata.track_total = metadata.track_total.or(total);
+                    continue;
+                }
+                "TRACKTOTAL" | "TOTALTRACKS" => {
+                    metadata.track_total = metadata.track_total.or(value.parse().ok());
+                    continue;
+                }
+                "DISCNUMBER" => {
+                    let (number, total) = parse_position(&value);
+                    metadata.disc_number = metadata.disc_number.or(number);
+                    metadata.disc_total = metadata.disc_total.or(total);
+                    continue;
+                }
+                "DISCTOTAL" | "TOTALDISCS" => {
+                    metadata.disc_total = metadata.disc_total.or(value.parse().ok());
+                    continue;
+                }
+                _ => continue,
+            };
+            field.get_or_insert(value);
+        }
+
+        // "2003-05-12", "2003" or an ISO timestamp; keep the date part.
+        if let Some(date) = date {
+            metadata.year = date.get(..4).and_then(|year| year.parse().ok());
+            let day = date.split('T').next().unwrap_or(&date);
+            if day.len() > 4 {
+                metadata.date = Some(day.to_owned());
+            }
+        }
+        metadata
+    }
+
+    /// Reads just the tags, for listing files without opening them for playback.
+    /// Untagged or unreadable files give empty metadata.
+    pub fn read_from_path(path: impl AsRef<Path>) -> Self {
+        tags::read_from_path(path)
+            .ok()
+            .flatten()
+            .map(|tags| tags.metadata)
+            .unwrap_or_default()
+    }
+
+    /// Track artists joined for display, falling back to the album artist.
+    pub fn artist_line(&self) -> Option<String> {
+        if self.artists.is_empty() {
+            self.album_artist.clone()
+        } else {
+            Some(self.artists.join(", "))
+        }
+    }
+
+    /// "3/12", "3" or nothing.
+    pub fn track_position(&self) -> Option<String> {
+        let number = self.track_number?;
+        Some(match self.track_total {
+            Some(total) => format!("{}/{}", number, total),
+            None => number.to_string(),
+        })
+    }
+
+    /// "Album (2003)", "Album" or just the year.
+    pub fn album_line(&self) -> Option<String> {
+        match (&self.album, self.year) {
+            (Some(album), Some(year)) => Some(format!("{} ({})", album, year)),
+            (Some(album), None) => Some(album.clone()),
+            (None, Some(year)) => Some(year.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    /// Labelled fields that are present, in display order, for tooltips and info panels.
+    pub fn details(&self) -> Vec<(&'static str, String)> {
+        let disc = self.disc_number.map(|number| match self.disc_total {
+            Some(total) => format!("{}/{}", number, total),
+            None => number.to_string(),
+        });
+        let date = self.date.clone().or(self.year.map(|year| year.to_string()));
+
+        [
+            ("Title", self.title.clone()),
+            ("Artist", self.artist_line()),
+            ("Album", self.album.clone()),
+            ("Album artist", self.album_artist.clone()),
+            ("Track", self.track_position()),
+            ("Disc", disc),
+            ("Date", date),
+            ("Genre", self.genre.clone()),
+            ("Composer", self.composer.clone()),
+            ("Comment", self.comment.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| Some((label, value?)))
+        .collect()
+    }
+}
+
+/// "3/12" or "3".
+fn parse_position(value: &str) -> (Option<u32>, Option<u32>) {
+    let (number, total) = match value.split_once('/') {
+        Some((number, total)) => (number, Some(total)),
+        None => (value, None),
+    };
+    (
+        number.trim().parse().ok(),
+        total.and_then(|total| total.trim().parse().ok()),
+    )
+}
+
+/// Tags are often present but blank; treat those as missing.
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_owned)
+}
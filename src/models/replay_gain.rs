@@ -0,0 +1,86 @@
+use id3::Tag;
+
+/// ReplayGain adjustments stored in a file's tags, in dB relative to the
+/// ReplayGain reference level (-18 LUFS) and as linear sample peaks.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReplayGain {
+    pub track_gain: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
+impl ReplayGain {
+    /// R128 gains are relative to -23 LUFS; ReplayGain 2.0 uses -18 LUFS.
+    const R128_TO_REPLAYGAIN_DB: f32 = 5.0;
+
+    /// Reads `TXXX:REPLAYGAIN_*` / `TXXX:R128_*` frames.
+    pub fn from_id3(tag: &Tag) -> Self {
+        Self::from_pairs(
+            tag.extended_texts()
+                .map(|text| (text.description.as_str(), text.value.as_str())),
+        )
+    }
+
+    /// Reads `REPLAYGAIN_*` / `R128_*` Vorbis comment fields.
+    pub fn from_comments(comments: &[(String, String)]) -> Self {
+        Self::from_pairs(comments.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+    }
+
+    fn from_pairs<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut gain = Self::default();
+        let mut r128_track = None;
+        let mut r128_album = None;
+
+        for (key, value) in pairs {
+            match key.to_ascii_uppercase().as_str() {
+                "REPLAYGAIN_TRACK_GAIN" => gain.track_gain = parse_db(value),
+                "REPLAYGAIN_TRACK_PEAK" => gain.track_peak = parse_number(value),
+                "REPLAYGAIN_ALBUM_GAIN" => gain.album_gain = parse_db(value),
+                "REPLAYGAIN_ALBUM_PEAK" => gain.album_peak = parse_number(value),
+                "R128_TRACK_GAIN" => r128_track = parse_q7_8(value),
+                "R128_ALBUM_GAIN" => r128_album = parse_q7_8(value),
+                _ => {}
+            }
+        }
+
+        // Classic ReplayGain fields win when a file carries both.
+        gain.track_gain = gain
+            .track_gain
+            .or(r128_track.map(|db| db + Self::R128_TO_REPLAYGAIN_DB));
+        gain.album_gain = gain
+            .album_gain
+            .or(r128_album.map(|db| db + Self::R128_TO_REPLAYGAIN_DB));
+        gain
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.track_gain.is_none() && self.album_gain.is_none()
+    }
+}
+
+/// Parses values like `-6.54 dB` or `+1.2`.
+fn parse_db(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let number = value
+        .strip_suffix("dB")
+        .or_else(|| value.strip_suffix("db"))
+        .or_else(|| value.strip_suffix("DB"))
+        .unwrap_or(value);
+    parse_number(number)
+}
+
+fn parse_number(value: &str) -> Option<f32> {
+    value
+        .trim()
+        .trim_start_matches('+')
+        .parse::<f32>()
+        .ok()
+        .filter(|v| v.is_finite())
+}
+
+/// R128 gains are signed Q7.8 fixed-point integers in dB.
+fn parse_q7_8(value: &str) -> Option<f32> {
+    let raw: i16 = value.trim().parse().ok()?;
+    Some(raw as f32 / 256.0)
+}
@@ -0,0 +1,111 @@
+pub mod ape;
+pub mod batch;
+pub mod mp4;
+pub mod picture;
+pub mod vorbis;
+pub mod writer;
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use id3::Tag;
+
+use crate::models::{metadata::TrackMetadata, replay_gain::ReplayGain, tags::picture::Picture};
+
+/// `KEY=value` pairs from a Vorbis comment block, APEv2 tag or MP4 item list, under
+/// Vorbis comment names. Keys are upper-cased, since every format treats them as
+/// case-insensitive.
+pub type Comments = Vec<(String, String)>;
+
+/// Text fields and embedded pictures of a non-ID3 tag.
+#[derive(Clone, Debug, Default)]
+pub struct FileTags {
+    pub comments: Comments,
+    pub pictures: Vec<Picture>,
+}
+
+/// Everything `Track` needs from a file's tags, whatever format they came in.
+#[derive(Clone, Debug, Default)]
+pub struct TrackTags {
+    pub metadata: TrackMetadata,
+    pub replay_gain: ReplayGain,
+    pub pictures: Vec<Picture>,
+}
+
+impl TrackTags {
+    pub fn from_id3(tag: &Tag) -> Self {
+        Self {
+            metadata: TrackMetadata::from_id3(tag),
+            replay_gain: ReplayGain::from_id3(tag),
+            pictures: tag.pictures().map(Picture::from_id3).collect(),
+        }
+    }
+
+    pub fn from_file_tags(tags: FileTags) -> Self {
+        Self {
+            metadata: TrackMetadata::from_comments(&tags.comments),
+            replay_gain: ReplayGain::from_comments(&tags.comments),
+            pictures: tags.pictures,
+        }
+    }
+}
+
+/// Reads the tags of an MP3 (ID3v2), FLAC, Ogg Vorbis, Opus, MP4/M4A or APEv2-tagged
+/// file, picking the reader from the file's magic bytes. Returns `Ok(None)` when the
+/// file carries no tag we understand.
+pub fn read_from_path(path: impl AsRef<Path>) -> io::Result<Option<TrackTags>> {
+    let path = path.as_ref();
+    let mut magic = [0u8; 8];
+    let read = File::open(path)?.read(&mut magic)?;
+    let magic = &magic[..read];
+
+    if magic.starts_with(b"ID3") {
+        return read_id3(path);
+    }
+    if magic.starts_with(b"fLaC") || magic.starts_with(b"OggS") {
+        return Ok(vorbis::read_from_path(path)?.map(TrackTags::from_file_tags));
+    }
+    if magic.get(4..8) == Some(b"ftyp") {
+        return Ok(mp4::read_from_path(path)?.map(TrackTags::from_file_tags));
+    }
+    // Monkey's Audio, WavPack and Musepack, and the odd MP3, keep an APEv2 tag at the end.
+    if let Some(tags) = ape::read_from_path(path)? {
+        return Ok(Some(TrackTags::from_file_tags(tags)));
+    }
+    // ID3 chunks in WAV/AIFF and trailing ID3v1 tags.
+    read_id3(path)
+}
+
+fn read_id3(path: &Path) -> io::Result<Option<TrackTags>> {
+    id3::no_tag_ok(Tag::read_from_path(path))
+        .map(|tag| tag.as_ref().map(TrackTags::from_id3))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// --- BYTE CURSOR HELPERS ---
+
+pub(crate) fn take<'a>(cursor: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if cursor.len() < len {
+        return Err(invalid("truncated tag data"));
+    }
+    let (head, tail) = cursor.split_at(len);
+    *cursor = tail;
+    Ok(head)
+}
+
+pub(crate) fn take_u32_le(cursor: &mut &[u8]) -> io::Result<u32> {
+    let bytes = take(cursor, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+pub(crate) fn take_u32_be(cursor: &mut &[u8]) -> io::Result<u32> {
+    let bytes = take(cursor, 4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+pub(crate) fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
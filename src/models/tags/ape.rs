@@ -0,0 +1,106 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::models::tags::{
+    FileTags, invalid,
+    picture::{Picture, PictureKind},
+    take, take_u32_le,
+};
+
+const FOOTER_LEN: u64 = 32;
+const ID3V1_LEN: u64 = 128;
+/// Tags larger than this are treated as corrupt rather than buffered.
+const MAX_TAG_LEN: u64 = 64 * 1024 * 1024;
+
+/// Reads an APEv2 tag from the end of the file, or from just before a trailing
+/// ID3v1 tag, under Vorbis comment names. Returns `Ok(None)` when there is none.
+pub fn read_from_path(path: impl AsRef<Path>) -> io::Result<Option<FileTags>> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+
+    for trailer in [0, ID3V1_LEN] {
+        let Some(footer_at) = file_len.checked_sub(trailer + FOOTER_LEN) else {
+            continue;
+        };
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        file.seek(SeekFrom::Start(footer_at))?;
+        file.read_exact(&mut footer)?;
+        if !footer.starts_with(b"APETAGEX") {
+            continue;
+        }
+
+        let mut cursor = &footer[8..];
+        let _version = take_u32_le(&mut cursor)?;
+        // Items plus this footer, but not the optional header.
+        let tag_len = u64::from(take_u32_le(&mut cursor)?);
+        let count = take_u32_le(&mut cursor)?;
+        let items_len = tag_len
+            .checked_sub(FOOTER_LEN)
+            .filter(|len| *len <= MAX_TAG_LEN && *len <= footer_at)
+            .ok_or_else(|| invalid("bad APEv2 tag size"))?;
+
+        let mut items = vec![0u8; items_len as usize];
+        file.seek(SeekFrom::Start(footer_at - items_len))?;
+        file.read_exact(&mut items)?;
+        return parse_items(&items, count).map(Some);
+    }
+    Ok(None)
+}
+
+fn parse_items(data: &[u8], count: u32) -> io::Result<FileTags> {
+    let mut cursor = data;
+    let mut tags = FileTags::default();
+
+    for _ in 0..count {
+        let len = take_u32_le(&mut cursor)? as usize;
+        let flags = take_u32_le(&mut cursor)?;
+        let key_len = cursor
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| invalid("unterminated APEv2 key"))?;
+        let key = String::from_utf8_lossy(take(&mut cursor, key_len)?).to_ascii_uppercase();
+        take(&mut cursor, 1)?;
+        let value = take(&mut cursor, len)?;
+
+        match (flags >> 1) & 0b11 {
+            // UTF-8 text; several values are separated by NULs.
+            0 => {
+                for part in String::from_utf8_lossy(value).split('\0') {
+                    tags.comments
+                        .push((vorbis_key(&key).to_owned(), part.to_owned()));
+                }
+            }
+            // Binary: cover art is a file name, a NUL, then the image.
+            1 if key.starts_with("COVER ART") => {
+                let image = value
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .map_or(value, |end| &value[end + 1..]);
+                let kind = match key.as_str() {
+                    "COVER ART (FRONT)" => PictureKind::FrontCover,
+                    "COVER ART (BACK)" => PictureKind::BackCover,
+                    _ => PictureKind::Other,
+                };
+                if !image.is_empty() {
+                    tags.pictures.push(Picture::new(kind, image.to_vec()));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(tags)
+}
+
+/// APEv2 item names that differ from their Vorbis comment counterparts.
+fn vorbis_key(key: &str) -> &str {
+    match key {
+        "ALBUM ARTIST" => "ALBUMARTIST",
+        "TRACK" => "TRACKNUMBER",
+        "DISC" => "DISCNUMBER",
+        "YEAR" => "DATE",
+        other => other,
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use crate::models::{
+    metadata::TrackMetadata,
+    tags::writer::{FieldChange, TagChanges},
+};
+
+/// Fields set on every selected track at once, plus optional renumbering in the
+/// order the tracks are passed in, which for the library is list order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchEdit {
+    /// Only album, album artist, genre and year make sense to share.
+    pub shared: TagChanges,
+    /// Numbers the tracks 1/n to n/n.
+    pub number_tracks: bool,
+}
+
+/// What a batch edit will write to one file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedEdit {
+    pub path: PathBuf,
+    pub changes: TagChanges,
+    pub diff: Vec<FieldChange>,
+}
+
+impl BatchEdit {
+    /// The changes each file needs, skipping files already tagged that way.
+    pub fn plan(&self, tracks: &[(PathBuf, TrackMetadata)]) -> Vec<PlannedEdit> {
+        let total = tracks.len();
+        tracks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (path, metadata))| {
+                let mut changes = self.shared.clone();
+                if self.number_tracks {
+                    changes.track = Some(format!("{}/{}", index + 1, total));
+                }
+                let diff = changes.diff(metadata);
+                if diff.is_empty() {
+                    return None;
+                }
+                Some(PlannedEdit {
+                    path: path.clone(),
+                    changes: changes.relative_to(metadata),
+                    diff,
+                })
+            })
+            .collect()
+    }
+
+    /// The value every track agrees on for each shareable field, to prefill the form.
+    pub fn common_values(tracks: &[TrackMetadata]) -> TagChanges {
+        let common = |field: fn(&TrackMetadata) -> Option<String>| {
+            let mut values = tracks.iter().map(field);
+            let first = values.next()?;
+            values
+                .all(|value| value == first)
+                .then_some(first)
+                .flatten()
+        };
+        TagChanges {
+            album: common(|m| m.album.clone()),
+            album_artist: common(|m| m.album_artist.clone()),
+            genre: common(|m| m.genre.clone()),
+            year: common(|m| m.year.map(|year| year.to_string())),
+            ..TagChanges::default()
+        }
+    }
+}
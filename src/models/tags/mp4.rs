@@ -0,0 +1,236 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use id3::TagLike;
+
+use crate::models::tags::{
+    FileTags, invalid,
+    picture::{Picture, PictureKind},
+    take, take_u32_be,
+};
+
+/// Movie headers larger than this are treated as corrupt rather than buffered.
+const MAX_MOOV_LEN: u64 = 64 * 1024 * 1024;
+
+/// `data` atom type indicators.
+const UTF8: u32 = 1;
+const JPEG: u32 = 13;
+const PNG: u32 = 14;
+const BMP: u32 = 27;
+
+/// Reads the iTunes-style item list (`moov/udta/meta/ilst`) of an MP4/M4A file,
+/// under Vorbis comment names. Returns `Ok(None)` when the file has no item list.
+pub fn read_from_path(path: impl AsRef<Path>) -> io::Result<Option<FileTags>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let Some(moov) = find_moov(&mut reader)? else {
+        return Ok(None);
+    };
+
+    let ilst = child(&moov, b"udta")
+        .and_then(|udta| child(udta, b"meta"))
+        .map(meta_children)
+        .and_then(|meta| child(meta, b"ilst"))
+        .or_else(|| {
+            child(&moov, b"meta")
+                .map(meta_children)
+                .and_then(|meta| child(meta, b"ilst"))
+        });
+    let Some(ilst) = ilst else {
+        return Ok(None);
+    };
+
+    let mut tags = FileTags::default();
+    for (name, item) in atoms(ilst) {
+        read_item(&name, item, &mut tags);
+    }
+    Ok(Some(tags))
+}
+
+/// Skips top-level atoms (the media data can be gigabytes) until `moov`.
+fn find_moov(reader: &mut (impl Read + Seek)) -> io::Result<Option<Vec<u8>>> {
+    loop {
+        let mut header = [0u8; 8];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let name = [header[4], header[5], header[6], header[7]];
+        let (len, header_len) =
+            match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                // Runs to the end of the file, so nothing follows it.
+                0 => return Ok(None),
+                1 => {
+                    let mut large = [0u8; 8];
+                    reader.read_exact(&mut large)?;
+                    (u64::from_be_bytes(large), 16)
+                }
+                len => (u64::from(len), 8),
+            };
+        let body_len = len
+            .checked_sub(header_len)
+            .ok_or_else(|| invalid("MP4 atom shorter than its header"))?;
+
+        if &name == b"moov" {
+            if body_len > MAX_MOOV_LEN {
+                return Err(invalid("MP4 movie header too large"));
+            }
+            let mut moov = vec![0u8; body_len as usize];
+            reader.read_exact(&mut moov)?;
+            return Ok(Some(moov));
+        }
+        let skip = i64::try_from(body_len).map_err(|_| invalid("MP4 atom too large"))?;
+        reader.seek(SeekFrom::Current(skip))?;
+    }
+}
+
+/// Child atoms of an in-memory atom body. Stops quietly at the first malformed one.
+fn atoms(mut data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    std::iter::from_fn(move || {
+        let mut cursor = data;
+        let len = take_u32_be(&mut cursor).ok()? as usize;
+        let name = take(&mut cursor, 4).ok()?;
+        let body = take(&mut cursor, len.checked_sub(8)?).ok()?;
+        data = cursor;
+        Some(([name[0], name[1], name[2], name[3]], body))
+    })
+}
+
+fn child<'a>(data: &'a [u8], name: &[u8; 4]) -> Option<&'a [u8]> {
+    atoms(data).find(|(n, _)| n == name).map(|(_, body)| body)
+}
+
+/// `meta` is a full atom (version and flags first) in MP4, a plain one in QuickTime.
+fn meta_children(meta: &[u8]) -> &[u8] {
+    if meta.get(4..8) == Some(b"hdlr") {
+        meta
+    } else {
+        meta.get(4..).unwrap_or_default()
+    }
+}
+
+/// `(type indicator, payload)` of each `data` atom in an item.
+fn data_atoms(item: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    atoms(item)
+        .filter(|(name, _)| name == b"data")
+        .filter_map(|(_, mut body)| {
+            let kind = take_u32_be(&mut body).ok()? & 0x00ff_ffff;
+            // locale
+            take(&mut body, 4).ok()?;
+            Some((kind, body))
+        })
+}
+
+fn read_item(name: &[u8; 4], item: &[u8], tags: &mut FileTags) {
+    let key = match name {
+        b"\xa9nam" => "TITLE",
+        b"\xa9ART" => "ARTIST",
+        b"aART" => "ALBUMARTIST",
+        b"\xa9alb" => "ALBUM",
+        b"\xa9day" => "DATE",
+        b"\xa9gen" => "GENRE",
+        b"\xa9wrt" => "COMPOSER",
+        b"\xa9cmt" => "COMMENT",
+        b"trkn" => return read_pair(item, "TRACKNUMBER", "TRACKTOTAL", tags),
+        b"disk" => return read_pair(item, "DISCNUMBER", "DISCTOTAL", tags),
+        b"gnre" => return read_genre_number(item, tags),
+        b"covr" => return read_covers(item, tags),
+        b"----" => return read_freeform(item, tags),
+        _ => return,
+    };
+    for (kind, payload) in data_atoms(item) {
+        if kind == UTF8 {
+            let value = String::from_utf8_lossy(payload).into_owned();
+            tags.comments.push((key.to_owned(), value));
+        }
+    }
+}
+
+/// `trkn`/`disk`: two padding bytes, then big-endian number and total.
+fn read_pair(item: &[u8], number_key: &str, total_key: &str, tags: &mut FileTags) {
+    let Some((_, payload)) = data_atoms(item).next() else {
+        return;
+    };
+    let field = |at: usize| {
+        payload
+            .get(at..at + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .filter(|value| *value > 0)
+    };
+    if let Some(number) = field(2) {
+        tags.comments
+            .push((number_key.to_owned(), number.to_string()));
+    }
+    if let Some(total) = field(4) {
+        tags.comments
+            .push((total_key.to_owned(), total.to_string()));
+    }
+}
+
+/// `gnre` stores an ID3v1 genre index plus one.
+fn read_genre_number(item: &[u8], tags: &mut FileTags) {
+    let Some((_, payload)) = data_atoms(item).next() else {
+        return;
+    };
+    let Some(&[high, low]) = payload.get(..2) else {
+        return;
+    };
+    let Some(index) = u16::from_be_bytes([high, low]).checked_sub(1) else {
+        return;
+    };
+    // Reuses the id3 crate's genre table rather than keeping a copy of it.
+    let mut tag = id3::Tag::new();
+    tag.set_genre(format!("({})", index));
+    if let Some(genre) = tag.genre_parsed() {
+        tags.comments.push(("GENRE".to_owned(), genre.into_owned()));
+    }
+}
+
+fn read_covers(item: &[u8], tags: &mut FileTags) {
+    for (kind, payload) in data_atoms(item) {
+        if matches!(kind, JPEG | PNG | BMP) || kind == 0 {
+            tags.pictures
+                .push(Picture::new(PictureKind::FrontCover, payload.to_vec()));
+        }
+    }
+}
+
+/// `----` items carry a reverse-DNS `mean`, a `name` and the value, e.g.
+/// `com.apple.iTunes:replaygain_track_gain`.
+fn read_freeform(item: &[u8], tags: &mut FileTags) {
+    // Both are full atoms.
+    let Some(name) = child(item, b"name").and_then(|name| name.get(4..)) else {
+        return;
+    };
+    let key = String::from_utf8_lossy(name).to_ascii_uppercase();
+    for (kind, payload) in data_atoms(item) {
+        if kind == UTF8 {
+            let value = String::from_utf8_lossy(payload).into_owned();
+            tags.comments.push((key.clone(), value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn huge_atoms_are_rejected_instead_of_seeking_backwards() {
+        let mut file = Vec::new();
+        file.extend_from_slice(&8u32.to_be_bytes());
+        file.extend_from_slice(b"free");
+        // A 64-bit length that would wrap to a negative seek.
+        file.extend_from_slice(&1u32.to_be_bytes());
+        file.extend_from_slice(b"mdat");
+        file.extend_from_slice(&(u64::MAX - 7).to_be_bytes());
+
+        let error = find_moov(&mut Cursor::new(file)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
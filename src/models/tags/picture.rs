@@ -0,0 +1,114 @@
+use std::io;
+
+use id3::frame::PictureType;
+
+use crate::models::tags::{invalid, take, take_u32_be};
+
+/// What an embedded picture shows. Declared in the order the artwork viewer
+/// lists them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PictureKind {
+    FrontCover,
+    BackCover,
+    /// A booklet or leaflet page.
+    Leaflet,
+    /// The disc itself.
+    Media,
+    /// The lead artist, band, conductor, composer or lyricist.
+    Artist,
+    Other,
+}
+
+impl PictureKind {
+    /// From the ID3v2 APIC picture type, which FLAC reuses.
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            3 => PictureKind::FrontCover,
+            4 => PictureKind::BackCover,
+            5 => PictureKind::Leaflet,
+            6 => PictureKind::Media,
+            7..=12 => PictureKind::Artist,
+            _ => PictureKind::Other,
+        }
+    }
+}
+
+impl From<PictureType> for PictureKind {
+    fn from(picture_type: PictureType) -> Self {
+        match picture_type {
+            PictureType::CoverFront => PictureKind::FrontCover,
+            PictureType::CoverBack => PictureKind::BackCover,
+            PictureType::Leaflet => PictureKind::Leaflet,
+            PictureType::Media => PictureKind::Media,
+            PictureType::LeadArtist
+            | PictureType::Artist
+            | PictureType::Conductor
+            | PictureType::Band
+            | PictureType::Composer
+            | PictureType::Lyricist => PictureKind::Artist,
+            _ => PictureKind::Other,
+        }
+    }
+}
+
+/// An image embedded in a file's tags.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Picture {
+    pub kind: PictureKind,
+    pub mime_type: String,
+    pub description: String,
+    pub data: Vec<u8>,
+}
+
+impl Picture {
+    /// A picture whose container only stores the bytes; the MIME type is sniffed.
+    pub fn new(kind: PictureKind, data: Vec<u8>) -> Self {
+        let mime_type = match data.as_slice() {
+            [0x89, b'P', b'N', b'G', ..] => "image/png",
+            [0xff, 0xd8, ..] => "image/jpeg",
+            [b'G', b'I', b'F', ..] => "image/gif",
+            [b'B', b'M', ..] => "image/bmp",
+            _ => "application/octet-stream",
+        };
+        Self {
+            kind,
+            mime_type: mime_type.to_owned(),
+            description: String::new(),
+            data,
+        }
+    }
+
+    pub fn from_id3(picture: &id3::frame::Picture) -> Self {
+        Self {
+            kind: picture.picture_type.into(),
+            mime_type: picture.mime_type.clone(),
+            description: picture.description.clone(),
+            data: picture.data.clone(),
+        }
+    }
+
+    /// Parses a FLAC `PICTURE` block, also found base64-encoded in the
+    /// `METADATA_BLOCK_PICTURE` Vorbis comment of Ogg files.
+    pub fn from_flac_block(block: &[u8]) -> io::Result<Self> {
+        let mut cursor = block;
+        let kind = PictureKind::from_code(take_u32_be(&mut cursor)?);
+        let mime_len = take_u32_be(&mut cursor)? as usize;
+        let mime_type = String::from_utf8_lossy(take(&mut cursor, mime_len)?).into_owned();
+        let description_len = take_u32_be(&mut cursor)? as usize;
+        let description = String::from_utf8_lossy(take(&mut cursor, description_len)?).into_owned();
+        // width, height, colour depth, palette size
+        take(&mut cursor, 16)?;
+        let data_len = take_u32_be(&mut cursor)? as usize;
+        let data = take(&mut cursor, data_len)?.to_vec();
+        if data.is_empty() {
+            return Err(invalid("empty FLAC picture"));
+        }
+
+        Ok(Self {
+            kind,
+            mime_type,
+            description,
+            data,
+        })
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::models::tags::{
+    Comments, FileTags, invalid,
+    picture::{Picture, PictureKind},
+    take, take_u32_le,
+};
+
+/// Comment headers larger than this are treated as corrupt rather than buffered.
+const MAX_PACKET_LEN: usize = 64 * 1024 * 1024;
+
+/// Reads the Vorbis comments and pictures of a FLAC, Ogg Vorbis or Ogg Opus file.
+/// Returns `Ok(None)` for any other container.
+pub fn read_from_path(path: impl AsRef<Path>) -> io::Result<Option<FileTags>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; 4];
+    if reader.read_exact(&mut magic).is_err() {
+        return Ok(None);
+    }
+
+    match &magic {
+        b"fLaC" => read_flac(&mut reader),
+        b"OggS" => read_ogg(&mut reader),
+        _ => Ok(None),
+    }
+}
+
+const VORBIS_COMMENT: u8 = 4;
+const PICTURE: u8 = 6;
+/// FLAC metadata block lengths are 24-bit.
+const MAX_BLOCK_LEN: usize = (1 << 24) - 1;
+
+fn read_flac(reader: &mut impl Read) -> io::Result<Option<FileTags>> {
+    let mut comments = None;
+    let mut pictures = Vec::new();
+    loop {
+        let (block_type, block, is_last) = read_flac_block(reader)?;
+        match block_type {
+            VORBIS_COMMENT => comments = Some(parse_comment_block(&block)?),
+            PICTURE => match Picture::from_flac_block(&block) {
+                Ok(picture) => pictures.push(picture),
+                Err(e) => eprintln!("Skipping unreadable FLAC picture: {e}"),
+            },
+            _ => {}
+        }
+        if is_last {
+            break;
+        }
+    }
+
+    if comments.is_none() && pictures.is_empty() {
+        return Ok(None);
+    }
+    let mut tags = split_pictures(comments.unwrap_or_default());
+    tags.pictures.extend(pictures);
+    Ok(Some(tags))
+}
+
+fn read_flac_block(reader: &mut impl Read) -> io::Result<(u8, Vec<u8>, bool)> {
+    let mut header = [0u8; 4];
+    reader.read_exact(&mut header)?;
+    let is_last = header[0] & 0x80 != 0;
+    let block_type = header[0] & 0x7f;
+    let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+
+    let mut block = vec![0u8; len];
+    reader.read_exact(&mut block)?;
+    Ok((block_type, block, is_last))
+}
+
+/// Copies the FLAC file at `source` to `target` with its Vorbis comments passed
+/// through `edit`. Every other metadata block and the audio are copied as they are.
+pub(crate) fn rewrite_flac(
+    source: &Path,
+    target: &Path,
+    edit: impl FnOnce(&mut Comments),
+) -> io::Result<()> {
+    let mut reader = BufReader::new(File::open(source)?);
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"fLaC" {
+        return Err(invalid("not a FLAC file"));
+    }
+
+    let mut blocks = Vec::new();
+    let mut vendor = String::from("music_player");
+    let mut comments = Comments::new();
+    let mut comment_at = None;
+    loop {
+        let (block_type, block, is_last) = read_flac_block(&mut reader)?;
+        if block_type == VORBIS_COMMENT {
+            (vendor, comments) = parse_comment_block_with_vendor(&block)?;
+            comment_at = Some(blocks.len());
+        } else {
+            blocks.push((block_type, block));
+        }
+        if is_last {
+            break;
+        }
+    }
+
+    edit(&mut comments);
+    // STREAMINFO has to stay first.
+    let at = comment_at.unwrap_or(1).min(blocks.len());
+    blocks.insert(at, (VORBIS_COMMENT, encode_comment_block(&vendor, &comments)));
+
+    let mut writer = BufWriter::new(File::create(target)?);
+    writer.write_all(b"fLaC")?;
+    let count = blocks.len();
+    for (i, (block_type, block)) in blocks.into_iter().enumerate() {
+        if block.len() > MAX_BLOCK_LEN {
+            return Err(invalid("FLAC metadata block too large"));
+        }
+        let last = if i + 1 == count { 0x80 } else { 0 };
+        let len = (block.len() as u32).to_be_bytes();
+        writer.write_all(&[last | block_type, len[1], len[2], len[3]])?;
+        writer.write_all(&block)?;
+    }
+    io::copy(&mut reader, &mut writer)?;
+    writer.flush()
+}
+
+/// Walks Ogg pages until the second packet (the comment header) of the first stream.
+/// The "OggS" capture pattern of the first page has already been consumed.
+fn read_ogg(reader: &mut impl Read) -> io::Result<Option<FileTags>> {
+    let mut packets: Vec<Vec<u8>> = vec![Vec::new()];
+    let mut first_page = true;
+
+    while packets.len() <= 2 {
+        if !first_page {
+            let mut magic = [0u8; 4];
+            reader.read_exact(&mut magic)?;
+            if &magic != b"OggS" {
+                return Err(invalid("missing Ogg capture pattern"));
+            }
+        }
+        first_page = false;
+
+        // version, header type, granule position, serial, sequence, checksum
+        let mut header = [0u8; 23];
+        reader.read_exact(&mut header)?;
+        let segment_count = header[22] as usize;
+        let mut lacing = vec![0u8; segment_count];
+        reader.read_exact(&mut lacing)?;
+
+        for len in lacing {
+            let mut segment = vec![0u8; len as usize];
+            reader.read_exact(&mut segment)?;
+            let packet = packets.last_mut().expect("always one open packet");
+            packet.extend_from_slice(&segment);
+            if packet.len() > MAX_PACKET_LEN {
+                return Err(invalid("Ogg header packet too large"));
+            }
+            // A lacing value below 255 terminates the packet.
+            if len < 255 {
+                packets.push(Vec::new());
+            }
+        }
+    }
+
+    let comment = &packets[1];
+    let body = comment
+        .strip_prefix(b"\x03vorbis")
+        .or_else(|| comment.strip_prefix(b"OpusTags"));
+    match body {
+        Some(body) => Ok(Some(split_pictures(parse_comment_block(body)?))),
+        None => Ok(None),
+    }
+}
+
+/// Moves base64-encoded cover art out of the comments: `METADATA_BLOCK_PICTURE`
+/// holds a FLAC picture block, the older `COVERART` just the image.
+fn split_pictures(comments: Comments) -> FileTags {
+    let mut tags = FileTags::default();
+    for (key, value) in comments {
+        let picture = match key.as_str() {
+            "METADATA_BLOCK_PICTURE" => {
+                decode_base64(&value).and_then(|block| Picture::from_flac_block(&block).ok())
+            }
+            "COVERART" => decode_base64(&value).map(|data| Picture::new(PictureKind::FrontCover, data)),
+            _ => {
+                tags.comments.push((key, value));
+                continue;
+            }
+        };
+        match picture {
+            Some(picture) => tags.pictures.push(picture),
+            None => eprintln!("Skipping unreadable {key} comment"),
+        }
+    }
+    tags
+}
+
+/// Standard-alphabet base64, padding optional. Whitespace is ignored.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((buffer >> bits) as u8);
+        }
+    }
+    Some(data)
+}
+
+/// Parses the vendor string and `KEY=value` list shared by every Vorbis comment block.
+pub fn parse_comment_block(data: &[u8]) -> io::Result<Comments> {
+    parse_comment_block_with_vendor(data).map(|(_, comments)| comments)
+}
+
+fn parse_comment_block_with_vendor(data: &[u8]) -> io::Result<(String, Comments)> {
+    let mut cursor = data;
+    let vendor_len = take_u32_le(&mut cursor)? as usize;
+    let vendor = String::from_utf8_lossy(take(&mut cursor, vendor_len)?).into_owned();
+
+    let count = take_u32_le(&mut cursor)?;
+    let mut comments = Vec::new();
+    for _ in 0..count {
+        let len = take_u32_le(&mut cursor)? as usize;
+        let entry = String::from_utf8_lossy(take(&mut cursor, len)?);
+        if let Some((key, value)) = entry.split_once('=') {
+            comments.push((key.to_ascii_uppercase(), value.to_string()));
+        }
+    }
+    Ok((vendor, comments))
+}
+
+pub fn encode_comment_block(vendor: &str, comments: &[(String, String)]) -> Vec<u8> {
+    let mut block = Vec::new();
+    block.extend((vendor.len() as u32).to_le_bytes());
+    block.extend(vendor.as_bytes());
+    block.extend((comments.len() as u32).to_le_bytes());
+    for (key, value) in comments {
+        let entry = format!("{}={}", key, value);
+        block.extend((entry.len() as u32).to_le_bytes());
+        block.extend(entry.as_bytes());
+    }
+    block
+}
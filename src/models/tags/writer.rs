@@ -0,0 +1,379 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
+
+use id3::{Tag, TagLike, Version, frame::Comment, frame::Timestamp};
+
+use crate::models::{
+    metadata::TrackMetadata,
+    tags::{self, Comments, TrackTags, vorbis},
+};
+
+/// Edits to write back to a file's tags, as typed. `None` leaves a field alone;
+/// an empty value removes it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TagChanges {
+    pub title: Option<String>,
+    /// Several artists are separated by `;`.
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    /// "3" or "3/12".
+    pub track: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// One field's value before and after an edit, for previewing it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+impl TagChanges {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Labelled fields in display order.
+    pub fn fields(&self) -> [(&'static str, &Option<String>); 8] {
+        [
+            ("Title", &self.title),
+            ("Artist", &self.artist),
+            ("Album", &self.album),
+            ("Album artist", &self.album_artist),
+            ("Track", &self.track),
+            ("Genre", &self.genre),
+            ("Year", &self.year),
+            ("Comment", &self.comment),
+        ]
+    }
+
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut Option<String>); 8] {
+        [
+            ("Title", &mut self.title),
+            ("Artist", &mut self.artist),
+            ("Album", &mut self.album),
+            ("Album artist", &mut self.album_artist),
+            ("Track", &mut self.track),
+            ("Genre", &mut self.genre),
+            ("Year", &mut self.year),
+            ("Comment", &mut self.comment),
+        ]
+    }
+
+    /// Drops the fields that already hold these values in `metadata`.
+    pub fn relative_to(&self, metadata: &TrackMetadata) -> Self {
+        let current = editable_fields(metadata);
+        let mut changes = self.clone();
+        for ((_, change), (_, current)) in changes.fields_mut().into_iter().zip(current.fields()) {
+            if value(change) == value(current) {
+                *change = None;
+            }
+        }
+        changes
+    }
+
+    /// What writing these changes would do to a file tagged with `metadata`.
+    pub fn diff(&self, metadata: &TrackMetadata) -> Vec<FieldChange> {
+        let current = editable_fields(metadata);
+        let changes = self.relative_to(metadata);
+        changes
+            .fields()
+            .into_iter()
+            .zip(current.fields())
+            .filter_map(|((field, new), (_, old))| {
+                Some(FieldChange {
+                    field,
+                    old: value(old).unwrap_or_default().to_owned(),
+                    new: value(new)?.to_owned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Rejects a track number or year that wouldn't survive the round trip.
+    pub fn validate(&self) -> io::Result<()> {
+        if let Some(track) = self.track.as_deref()
+            && !track.trim().is_empty()
+            && parse_track(track).is_none()
+        {
+            return Err(invalid_input(format!(
+                "\"{}\" is not a track number",
+                track
+            )));
+        }
+        if let Some(year) = self.year.as_deref()
+            && !year.trim().is_empty()
+            && parse_year(year).is_none()
+        {
+            return Err(invalid_input(format!("\"{}\" is not a year", year)));
+        }
+        Ok(())
+    }
+
+    fn apply_to_id3(&self, tag: &mut Tag) {
+        if let Some(title) = value(&self.title) {
+            match title {
+                "" => tag.remove_title(),
+                title => tag.set_title(title),
+            }
+        }
+        if let Some(artist) = value(&self.artist) {
+            let artists = split_artists(artist);
+            if artists.is_empty() {
+                tag.remove_artist();
+            } else {
+                tag.set_text_values("TPE1", artists);
+            }
+        }
+        if let Some(album) = value(&self.album) {
+            match album {
+                "" => tag.remove_album(),
+                album => tag.set_album(album),
+            }
+        }
+        if let Some(album_artist) = value(&self.album_artist) {
+            match album_artist {
+                "" => tag.remove_album_artist(),
+                album_artist => tag.set_album_artist(album_artist),
+            }
+        }
+        if let Some(track) = value(&self.track) {
+            tag.remove_track();
+            tag.remove_total_tracks();
+            if let Some((number, total)) = parse_track(track) {
+                tag.set_track(number);
+                if let Some(total) = total {
+                    tag.set_total_tracks(total);
+                }
+            }
+        }
+        if let Some(genre) = value(&self.genre) {
+            match genre {
+                "" => tag.remove_genre(),
+                genre => tag.set_genre(genre),
+            }
+        }
+        if let Some(year) = value(&self.year) {
+            // A new year makes any month and day tagged alongside it wrong.
+            tag.remove_year();
+            tag.remove_date_recorded();
+            if let Some(year) = parse_year(year) {
+                tag.set_date_recorded(Timestamp {
+                    year,
+                    month: None,
+                    day: None,
+                    hour: None,
+                    minute: None,
+                    second: None,
+                });
+            }
+        }
+        if let Some(comment) = value(&self.comment) {
+            tag.remove_comment(Some(""), None);
+            if !comment.is_empty() {
+                tag.add_frame(Comment {
+                    lang: "eng".to_owned(),
+                    description: String::new(),
+                    text: comment.to_owned(),
+                });
+            }
+        }
+    }
+
+    fn apply_to_comments(&self, comments: &mut Comments) {
+        let single = |value: &str| {
+            Some(value.to_owned())
+                .filter(|value| !value.is_empty())
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+
+        if let Some(title) = value(&self.title) {
+            replace(comments, &["TITLE"], single(title));
+        }
+        if let Some(artist) = value(&self.artist) {
+            let artists = split_artists(artist)
+                .into_iter()
+                .map(str::to_owned)
+                .collect();
+            replace(comments, &["ARTIST"], artists);
+        }
+        if let Some(album) = value(&self.album) {
+            replace(comments, &["ALBUM"], single(album));
+        }
+        if let Some(album_artist) = value(&self.album_artist) {
+            replace(
+                comments,
+                &["ALBUMARTIST", "ALBUM ARTIST"],
+                single(album_artist),
+            );
+        }
+        if let Some(track) = value(&self.track) {
+            let (number, total) = parse_track(track).unzip();
+            replace(
+                comments,
+                &["TRACKNUMBER"],
+                number.map(|n| n.to_string()).into_iter().collect(),
+            );
+            let total = total.flatten().map(|t| t.to_string());
+            replace(
+                comments,
+                &["TRACKTOTAL", "TOTALTRACKS"],
+                total.into_iter().collect(),
+            );
+        }
+        if let Some(genre) = value(&self.genre) {
+            replace(comments, &["GENRE"], single(genre));
+        }
+        if let Some(year) = value(&self.year) {
+            let year = parse_year(year).map(|year| year.to_string());
+            replace(comments, &["DATE", "YEAR"], year.into_iter().collect());
+        }
+        if let Some(comment) = value(&self.comment) {
+            replace(comments, &["COMMENT", "DESCRIPTION"], single(comment));
+        }
+    }
+}
+
+/// The editable fields as they are shown for editing, so unchanged fields can be
+/// told apart from edited ones.
+pub fn editable_fields(metadata: &TrackMetadata) -> TagChanges {
+    TagChanges {
+        title: Some(metadata.title.clone().unwrap_or_default()),
+        artist: Some(metadata.artists.join("; ")),
+        album: Some(metadata.album.clone().unwrap_or_default()),
+        album_artist: Some(metadata.album_artist.clone().unwrap_or_default()),
+        track: Some(metadata.track_position().unwrap_or_default()),
+        genre: Some(metadata.genre.clone().unwrap_or_default()),
+        year: Some(
+            metadata
+                .year
+                .map(|year| year.to_string())
+                .unwrap_or_default(),
+        ),
+        comment: Some(metadata.comment.clone().unwrap_or_default()),
+    }
+}
+
+/// Writes `changes` into the tags of the file at `path` and returns the tags as
+/// read back. MP3, WAV and AIFF get ID3v2 frames, FLAC its Vorbis comments.
+///
+/// The new file is written next to the original and renamed over it once it is
+/// on disk, so a crash part-way leaves the original untouched.
+pub fn write_to_path(path: impl AsRef<Path>, changes: &TagChanges) -> io::Result<TrackTags> {
+    let path = path.as_ref();
+    changes.validate()?;
+
+    let mut magic = [0u8; 4];
+    let read = File::open(path)?.read(&mut magic)?;
+    match &magic[..read] {
+        [b'f', b'L', b'a', b'C'] => replace_atomically(path, |temp| {
+            vorbis::rewrite_flac(path, temp, |comments| changes.apply_to_comments(comments))
+        })?,
+        // ID3v2, a bare MPEG frame sync, or the WAV/AIFF containers id3 can write into.
+        [b'I', b'D', b'3', ..] | [0xff, 0xe0..=0xff, ..] | b"RIFF" | b"FORM" => {
+            let existing = id3::no_tag_ok(Tag::read_from_path(path)).map_err(id3_error)?;
+            // ID3v2.2 can be read but not written, so those tags are upgraded to v2.3.
+            let version = match existing.as_ref().map(Tag::version) {
+                Some(Version::Id3v22) => Version::Id3v23,
+                Some(version) => version,
+                None => Version::Id3v24,
+            };
+            let mut tag = existing.unwrap_or_default();
+            changes.apply_to_id3(&mut tag);
+            replace_atomically(path, |temp| {
+                fs::copy(path, temp)?;
+                tag.write_to_path(temp, version).map_err(id3_error)
+            })?
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "editing tags is only supported for MP3, WAV, AIFF and FLAC files",
+            ));
+        }
+    }
+
+    Ok(tags::read_from_path(path)?.unwrap_or_default())
+}
+
+/// Lets `write` produce the new file at a temporary path beside `path`, then
+/// syncs it, renames it into place and syncs the directory so the rename sticks.
+fn replace_atomically(path: &Path, write: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| invalid_input(format!("{} is not a file", path.display())))?;
+    let temp = path.with_file_name(format!(".{}.tagedit", name.to_string_lossy()));
+
+    let result = write(&temp)
+        .and_then(|()| fs::set_permissions(&temp, fs::metadata(path)?.permissions()))
+        .and_then(|()| File::open(&temp)?.sync_all())
+        .and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result.and_then(|()| sync_parent(path))
+}
+
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => File::open(parent)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+/// Directories can't be opened as files here; the rename is as durable as it gets.
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn replace(comments: &mut Comments, keys: &[&str], values: Vec<String>) {
+    comments.retain(|(key, _)| !keys.contains(&key.as_str()));
+    comments.extend(values.into_iter().map(|value| (keys[0].to_owned(), value)));
+}
+
+fn value(field: &Option<String>) -> Option<&str> {
+    field.as_deref().map(str::trim)
+}
+
+fn split_artists(artists: &str) -> Vec<&str> {
+    artists
+        .split(';')
+        .map(str::trim)
+        .filter(|artist| !artist.is_empty())
+        .collect()
+}
+
+fn parse_track(track: &str) -> Option<(u32, Option<u32>)> {
+    match track.split_once('/') {
+        Some((number, total)) => Some((
+            number.trim().parse().ok()?,
+            Some(total.trim().parse().ok()?),
+        )),
+        None => Some((track.trim().parse().ok()?, None)),
+    }
+}
+
+fn parse_year(year: &str) -> Option<i32> {
+    year.trim()
+        .parse()
+        .ok()
+        .filter(|year| (1..=9999).contains(year))
+}
+
+fn id3_error(e: id3::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
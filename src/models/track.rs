@@ -1,16 +1,26 @@
-use id3::frame::PictureType;
-use id3::{Tag, TagLike};
 use image::ImageError;
+
+use crate::models::{
+    artwork,
+    metadata::TrackMetadata,
+    replay_gain::ReplayGain,
+    tags::{
+        self, TrackTags,
+        picture::{Picture, PictureKind},
+    },
+};
 use std::{
     fs::File,
     io::{self, BufReader, Result},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum MusicState {
     NotStarted,
     Playing,
+    Paused,
     Stopped,
     Completed,
 }
@@ -18,7 +28,9 @@ pub enum MusicState {
 pub struct Track {
     path: PathBuf,
     state: MusicState,
-    tag: Option<Tag>,
+    pictures: Vec<Picture>,
+    metadata: TrackMetadata,
+    replay_gain: ReplayGain,
 }
 
 impl Track {
@@ -28,26 +40,27 @@ impl Track {
         if !std::fs::exists(&path)? {
             return Ok(Self {
                 path,
-                tag: None,
+                pictures: Vec::new(),
                 state: MusicState::NotStarted,
+                metadata: TrackMetadata::default(),
+                replay_gain: ReplayGain::default(),
             });
         }
 
-        let tag = match Tag::read_from_path(&path) {
-            Ok(v) => {
-                println!("Tag is created");
-                Some(v)
-            }
+        let tags = match tags::read_from_path(&path) {
+            Ok(tags) => tags.unwrap_or_default(),
             Err(e) => {
-                println!("Failed to create tag! {e}");
-                None
+                eprintln!("Failed to read tags of {}: {e}", path.display());
+                TrackTags::default()
             }
         };
 
         Ok(Self {
             path,
-            tag,
+            pictures: tags.pictures,
             state: MusicState::NotStarted,
+            metadata: tags.metadata,
+            replay_gain: tags.replay_gain,
         })
     }
 
@@ -59,6 +72,10 @@ impl Track {
         self.state
     }
 
+    pub fn replay_gain(&self) -> &ReplayGain {
+        &self.replay_gain
+    }
+
     pub fn path(&self) -> &Path {
         &self.path
     }
@@ -66,45 +83,88 @@ impl Track {
     pub fn to_reader(&self) -> io::Result<BufReader<File>> {
         Ok(BufReader::new(File::open(&self.path)?))
     }
+    pub fn metadata(&self) -> &TrackMetadata {
+        &self.metadata
+    }
+
+    /// Takes freshly written tags, keeping the state and the decoder's length.
+    pub fn set_tags(&mut self, tags: TrackTags) {
+        let duration = self.metadata.duration;
+        self.metadata = tags.metadata;
+        self.metadata.duration = duration.or(self.metadata.duration);
+        self.replay_gain = tags.replay_gain;
+        self.pictures = tags.pictures;
+    }
+
+    /// The decoder's length, which beats a (possibly stale) TLEN frame.
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.metadata.duration = Some(duration);
+    }
+
+    /// The tagged title, or the file name without its extension.
     pub fn name(&self) -> String {
-        if let Some(tag) = &self.tag {
-            if let Some(name) = tag.title() {
-                name.to_string();
-            }
+        if let Some(title) = &self.metadata.title {
+            return title.clone();
         }
-        Path::new(&self.path)
+        self.path
             .file_stem()
-            .expect("Invalid Path")
-            .to_str()
+            .and_then(|stem| stem.to_str())
             .map(|s| s.to_string())
             .unwrap_or(String::from("Not named song"))
     }
 
+    /// The track artists, or the album artist.
     pub fn artist(&self) -> String {
-        if let Some(tag) = &self.tag {
-            if let Some(artist) = tag.artist() {
-                artist.to_string();
+        self.metadata
+            .artist_line()
+            .unwrap_or(String::from("No named artist"))
+    }
+
+    /// Pictures embedded in the tags, in file order.
+    pub fn pictures(&self) -> &[Picture] {
+        &self.pictures
+    }
+
+    /// The picture to show as the cover: the embedded front cover, any other
+    /// embedded picture (back covers last), then a cover image beside the file.
+    pub fn cover(&self) -> Option<Picture> {
+        let embedded = self.pictures.iter().min_by_key(|p| match p.kind {
+            PictureKind::FrontCover => 0,
+            PictureKind::BackCover => 2,
+            _ => 1,
+        });
+        if let Some(picture) = embedded {
+            return Some(picture.clone());
+        }
+        artwork::sidecar_images(&self.path)
+            .into_iter()
+            .filter(|path| artwork::cover_rank(path).is_some())
+            .find_map(|path| artwork::read_sidecar(&path).ok())
+    }
+
+    /// Every image that goes with the track, for browsing: embedded pictures and
+    /// image files beside it and in its scans folders, grouped by what they show
+    /// (front cover, back cover, booklet, disc, artist, the rest). Embedded
+    /// pictures come first within a group.
+    pub fn artwork(&self) -> Vec<Picture> {
+        let mut artwork = self.pictures.clone();
+        for path in artwork::sidecar_images(&self.path) {
+            match artwork::read_sidecar(&path) {
+                Ok(picture) => artwork.push(picture),
+                Err(e) => eprintln!("Failed to read {}: {e}", path.display()),
             }
         }
-        String::from("No named artist")
+        artwork.sort_by_key(|p| p.kind);
+        artwork
     }
 
     pub fn extract_img_bytes(&self) -> image::ImageResult<Vec<u8>> {
-        let tag = self.tag.as_ref().ok_or_else(|| {
+        let picture = self.cover().ok_or_else(|| {
             ImageError::IoError(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
-                "Tag not found",
+                "No cover picture",
             ))
         })?;
-        let picture = tag
-            .pictures()
-            .find(|p| p.picture_type == PictureType::CoverFront)
-            .ok_or_else(|| {
-                ImageError::IoError(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "No front cover picture",
-                ))
-            })?;
-        Ok(picture.data.clone())
+        Ok(picture.data)
     }
 }
@@ -1,70 +1,611 @@
 use image::{DynamicImage, ImageReader};
-use rodio::{OutputStream, Sink, Source};
+use rodio::{Decoder, Sink, Source};
 use std::{
-    io::Cursor,
+    fs::File,
+    io::{BufReader, Cursor},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        mpsc::{Receiver, Sender},
+    },
     time::Duration,
 };
 
-use crate::models::track::Track;
+use crate::audio::{
+    backend::{NullBackend, OutputBackend},
+    equalizer::{EqParams, EqualizerHandle},
+    fade, gain,
+    stretch::SpeedHandle,
+    transport::{self, TransportHandle},
+};
+use crate::models::{
+    tags::TrackTags,
+    track::{MusicState, Track},
+};
+
+pub mod ab_loop;
+pub mod controls;
+pub mod crossfade;
+pub mod equalizer;
+pub mod error;
+pub mod events;
+pub mod output;
+pub mod player;
+pub mod queue;
+pub mod replay_gain;
+pub mod resume_points;
+pub mod sleep_timer;
+pub mod volume;
+
+use ab_loop::AbLoop;
+use controls::SourceControls;
+use crossfade::{CrossfadeSettings, OutgoingTrack};
+use equalizer::{EqPreset, EqualizerState};
+use events::{EventBus, PlaybackEvent};
+use output::OutputDevice;
+use queue::{PlaybackMode, PlayQueue};
+use replay_gain::ReplayGainSettings;
+use resume_points::{ResumePoints, ResumeSettings};
+use sleep_timer::{Clock, SleepMode, SleepTimer};
+use volume::Volume;
+
+pub use error::{MusicOpenError, MusicOpenErrorKind};
 
-#[derive(Clone, Copy, Debug)]
-pub enum MusicOpenError {
-    OpenErr,
-    DecoderErr,
-    SinkErr,
-    // NoTagErr,
-    None,
+/// How close to the end of the current track the next one is appended to the sink.
+const GAPLESS_LOOKAHEAD: Duration = Duration::from_secs(5);
+
+/// A queued track whose decoder has already been appended behind the current one.
+struct PreloadedTrack {
+    index: usize,
+    track: Track,
+    total_duration: Option<Duration>,
+    controls: SourceControls,
 }
+
 pub struct MusicService {
-    stream_handle: OutputStream,
+    backend: Box<dyn OutputBackend>,
+    output: OutputDevice,
     pub music_file: Track,
     sink: Option<Sink>,
+    controls: Option<SourceControls>,
     total_duration: Option<Duration>,
+    queue: PlayQueue,
+    preloaded: Option<PreloadedTrack>,
+    preload_attempted: bool,
+    crossfade: CrossfadeSettings,
+    outgoing: Option<OutgoingTrack>,
+    volume: Volume,
+    replay_gain: ReplayGainSettings,
+    equalizer: EqualizerHandle,
+    /// Mirrors the handle's params, plus the user's presets; shared with snapshots.
+    equalizer_state: Arc<EqualizerState>,
+    speed: SpeedHandle,
+    /// Length of the ramps around pause, resume, stop and seek.
+    transport_ramp: Duration,
+    ab_loop: AbLoop,
+    resume_points: Arc<ResumePoints>,
+    /// Saved position of the current track, until the user takes it or dismisses it.
+    resume_offer: Option<Duration>,
+    sleep_timer: Option<SleepTimer>,
+    clock: Clock,
+    /// Volume factor from the sleep timer's fade-out, 1.0 when not fading.
+    sleep_level: f32,
+    last_error: Option<MusicOpenError>,
+    events: EventBus,
+    /// Position last published as a `PositionTick`.
+    last_tick: Option<Duration>,
+}
+
+impl Default for MusicService {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MusicService {
     pub fn new() -> Self {
-        let stream_handle =
-            rodio::OutputStreamBuilder::open_default_stream().expect("open default audio stream");
+        let mut output = OutputDevice::new();
+        // Without a device, keep running on a silent clock and retry from `update`.
+        let backend: Box<dyn OutputBackend> = match output.open_stream() {
+            Ok(stream) => Box::new(stream),
+            Err(e) => {
+                eprintln!("Audio output unavailable, continuing silently: {:?}", e);
+                Box::new(NullBackend::default())
+            }
+        };
+        Self::from_parts(output, backend)
+    }
+
+    /// A service playing into `backend` instead of a sound card, e.g. an
+    /// [`OfflineBackend`](crate::audio::backend::OfflineBackend) in tests.
+    pub fn with_backend(backend: impl OutputBackend + 'static) -> Self {
+        Self::from_parts(OutputDevice::new(), Box::new(backend))
+    }
+
+    fn from_parts(output: OutputDevice, backend: Box<dyn OutputBackend>) -> Self {
         Self {
-            stream_handle,
+            backend,
+            output,
             music_file: Track::new("").unwrap(),
             sink: None,
+            controls: None,
             total_duration: None,
+            queue: PlayQueue::new(),
+            preloaded: None,
+            preload_attempted: false,
+            crossfade: CrossfadeSettings::default(),
+            outgoing: None,
+            volume: Volume::default(),
+            replay_gain: ReplayGainSettings::default(),
+            equalizer: EqualizerHandle::new(EqParams::default()),
+            equalizer_state: Arc::default(),
+            speed: SpeedHandle::default(),
+            transport_ramp: transport::DEFAULT_RAMP,
+            ab_loop: AbLoop::default(),
+            resume_points: Arc::default(),
+            resume_offer: None,
+            sleep_timer: None,
+            clock: Clock::System,
+            sleep_level: 1.0,
+            last_error: None,
+            events: EventBus::new(),
+            last_tick: None,
         }
     }
 
     pub fn open(&mut self, file_path: impl AsRef<std::path::Path>) -> Result<(), MusicOpenError> {
-        
-        let path = file_path.as_ref();
-        self.music_file = Track::new(path).map_err(|_| MusicOpenError::OpenErr)?;
-        
-        let file = std::fs::File::open(path).map_err(|_| MusicOpenError::OpenErr)?;
+        let (track, source) =
+            Self::decode(file_path.as_ref()).inspect_err(|e| self.report(e.clone()))?;
+        let total_duration = source.total_duration();
+
+        let sink = rodio::Sink::connect_new(self.backend.mixer());
+        let (source, controls) = self.build_source(&track, source, 1.0);
+
+        // Only replace the loaded track once the new one is known to decode.
+        self.remember_position();
+        self.music_file = track;
+        self.total_duration = total_duration;
+        sink.append(source);
+        // Let whatever was playing ramp out instead of cutting it off mid-waveform.
+        if let Some(preloaded) = self.preloaded.take() {
+            preloaded.controls.transport.stop();
+        }
+        self.outgoing = match (self.sink.replace(sink), self.controls.replace(controls)) {
+            (Some(sink), Some(controls)) => {
+                controls.transport.stop();
+                Some(OutgoingTrack { sink, controls })
+            }
+            _ => None,
+        };
+        self.preload_attempted = false;
+        self.ab_loop = AbLoop::default();
+        self.apply_volume();
+        self.music_file.set_state(MusicState::Playing);
+        self.announce_track();
+
+        Ok(())
+    }
+
+    /// Reads the tags and probes a decoder for `path`.
+    ///
+    /// Gapless mode makes symphonia trim the encoder delay and padding recorded in
+    /// LAME/Xing headers, so consecutive MP3s join without silence.
+    fn decode(path: &Path) -> Result<(Track, Decoder<BufReader<File>>), MusicOpenError> {
+        let mut track = Track::new(path).map_err(|e| MusicOpenError::io(path, e))?;
+
+        let file = File::open(path).map_err(|e| MusicOpenError::io(path, e))?;
+        let byte_len = file
+            .metadata()
+            .map_err(|e| MusicOpenError::io(path, e))?
+            .len();
+
+        let source = Decoder::builder()
+            .with_data(BufReader::new(file))
+            .with_byte_len(byte_len)
+            .with_seekable(true)
+            .with_gapless(true)
+            .build()
+            .map_err(|e| MusicOpenError::decoder(path, e))?;
+        if let Some(duration) = source.total_duration() {
+            track.set_duration(duration);
+        }
+
+        Ok((track, source))
+    }
+
+    /// Wraps a decoder in the processing chain every track goes through: time
+    /// stretching, ReplayGain, the equalizer, the fade stage starting at
+    /// `initial_level`, then the transport controls.
+    fn build_source(
+        &self,
+        track: &Track,
+        decoder: Decoder<BufReader<File>>,
+        initial_level: f32,
+    ) -> (impl Source + Send + 'static, SourceControls) {
+        let tagged_gain = *track.replay_gain();
+        let (source, position) = self.speed.apply(decoder);
+        let (source, replay_gain) = gain::gain(source, self.replay_gain.factor(&tagged_gain));
+        let source = self.equalizer.apply(source);
+        let (source, fade) = fade::faded(source, initial_level);
+        let (source, transport) = transport::transport(source, self.transport_ramp);
+
+        let controls = SourceControls {
+            fade,
+            replay_gain,
+            tagged_gain,
+            position,
+            transport,
+        };
+        (source, controls)
+    }
+
+    pub fn resume(&mut self) {
+        match self.music_file.state() {
+            // The sink is drained once a track stops or completes, so start it over.
+            MusicState::Stopped | MusicState::Completed => {
+                let path = self.music_file.path().to_path_buf();
+                if let Err(e) = self.open(&path) {
+                    eprintln!("Failed to restart {:?}: {}", path, e);
+                }
+            }
+            _ => {
+                if let Some(controls) = &self.controls {
+                    controls.transport.play();
+                    self.music_file.set_state(MusicState::Playing);
+                    self.events.emit(PlaybackEvent::Playing);
+                }
+                if let Some(outgoing) = &self.outgoing {
+                    outgoing.controls.transport.play();
+                }
+            }
+        }
+    }
+    pub fn stop(&mut self) {
+        self.remember_position();
+        if let Some(controls) = &self.controls {
+            // Ramps out and ends the source; the sink is empty once it has.
+            controls.transport.stop();
+            if let Some(outgoing) = &self.outgoing {
+                outgoing.controls.transport.stop();
+            }
+            if let Some(preloaded) = self.preloaded.take() {
+                preloaded.controls.transport.stop();
+            }
+            self.music_file.set_state(MusicState::Stopped);
+            self.last_tick = None;
+            self.events.emit(PlaybackEvent::Stopped);
+        }
+    }
+    pub fn pause(&mut self) {
+        if let Some(controls) = &self.controls
+            && self.music_file.state() == MusicState::Playing
+        {
+            controls.transport.pause();
+            self.remember_position();
+            if let Some(outgoing) = &self.outgoing {
+                outgoing.controls.transport.pause();
+            }
+            self.music_file.set_state(MusicState::Paused);
+            self.events.emit(PlaybackEvent::Paused);
+        }
+    }
+
+    pub fn state(&self) -> MusicState {
+        self.music_file.state()
+    }
+
+    /// Detects the end of the current track and moves on to the next playable one
+    /// in the queue. Call once per frame.
+    pub fn update(&mut self) {
+        if self.output.needs_reopen(self.backend.is_audible()) {
+            self.reopen_output();
+        }
+        self.retire_outgoing();
+        self.promote_preloaded();
+        self.enforce_ab_loop();
+        self.run_sleep_timer();
+        // While looping, the next track must not start fading or queueing in, nor
+        // when the sleep timer is about to stop after this one.
+        let sleeps_after_track = self
+            .sleep_timer
+            .is_some_and(|timer| timer.ends_with_current_track());
+        if !self.ab_loop.is_active() && !sleeps_after_track {
+            if self.crossfade.is_enabled() {
+                self.start_crossfade();
+            } else {
+                self.preload_next();
+            }
+        }
+
+        let finished = self.music_file.state() == MusicState::Playing
+            && self.sink.as_ref().is_some_and(Sink::empty);
+        if !finished {
+            self.tick_position();
+            return;
+        }
+
+        self.music_file.set_state(MusicState::Completed);
+        self.announce_finished();
 
-        let source = rodio::Decoder::try_from(file).map_err(|_| MusicOpenError::DecoderErr)?;
+        // Library listings can contain non-audio files; skip anything that fails to open.
+        for index in self.queue.upcoming() {
+            let Some(path) = self.queue.follow(index).map(Path::to_path_buf) else {
+                continue;
+            };
+            match self.open(&path) {
+                Ok(_) => {
+                    // Pauses straight away if this was the sleep timer's last track.
+                    self.run_sleep_timer();
+                    return;
+                }
+                Err(e) => eprintln!("Skipping {:?}: {}", path, e),
+            }
+        }
+        // End of the queue: keep the finished track loaded so the bar stays visible.
+        self.music_file.set_state(MusicState::Completed);
+    }
+
+    /// Appends the next playable queued track to the same sink shortly before the
+    /// current one ends, so the sink moves on without a gap.
+    fn preload_next(&mut self) {
+        if self.preload_attempted || self.music_file.state() != MusicState::Playing {
+            return;
+        }
+        let Some(sink) = &self.sink else { return };
+
+        let near_end = match self.remaining_playback_time() {
+            Some(remaining) => remaining <= GAPLESS_LOOKAHEAD,
+            // Without a known length there is no "near the end", so queue it right away.
+            _ => true,
+        };
+        if !near_end {
+            return;
+        }
+        self.preload_attempted = true;
+
+        if let Some((index, track, source)) = self.decode_next_playable() {
+            let total_duration = source.total_duration();
+            let (source, controls) = self.build_source(&track, source, 1.0);
+            sink.append(source);
+            self.preloaded = Some(PreloadedTrack {
+                index,
+                track,
+                total_duration,
+                controls,
+            });
+        }
+    }
 
-        self.total_duration = source.total_duration();
+    /// Once the sink has drained the current source, the preloaded one is playing.
+    fn promote_preloaded(&mut self) {
+        let Some(sink) = &self.sink else { return };
+        if self.preloaded.is_none() || sink.len() > 1 {
+            return;
+        }
+        let Some(next) = self.preloaded.take() else { return };
 
-        let sink = rodio::Sink::connect_new(self.stream_handle.mixer());
+        self.announce_finished();
+        self.queue.follow(next.index);
+        self.controls = Some(next.controls);
+        self.music_file = next.track;
+        self.music_file.set_state(MusicState::Playing);
+        self.total_duration = next.total_duration;
+        self.preload_attempted = false;
+        self.ab_loop = AbLoop::default();
+        self.announce_track();
+    }
 
+    /// Probes the tracks due after the current one and returns the first that decodes.
+    fn decode_next_playable(&self) -> Option<(usize, Track, Decoder<BufReader<File>>)> {
+        for index in self.queue.upcoming() {
+            let path = &self.queue.tracks()[index];
+            match Self::decode(path) {
+                Ok((track, source)) => return Some((index, track, source)),
+                Err(e) => eprintln!("Skipping {:?}: {}", path, e),
+            }
+        }
+        None
+    }
+
+    /// Starts the next track on a second sink once the current one is within the
+    /// crossfade window, ramping one down while the other comes up.
+    fn start_crossfade(&mut self) {
+        if self.preload_attempted
+            || self.outgoing.is_some()
+            || self.music_file.state() != MusicState::Playing
+        {
+            return;
+        }
+        let Some(remaining) = self.remaining_playback_time() else {
+            return;
+        };
+        if remaining > self.crossfade.duration {
+            return;
+        }
+        self.preload_attempted = true;
+
+        let Some((index, track, source)) = self.decode_next_playable() else {
+            return;
+        };
+        let curve = self.crossfade.curve;
+
+        let total_duration = source.total_duration();
+        let sink = rodio::Sink::connect_new(self.backend.mixer());
+        let (source, controls) = self.build_source(&track, source, 0.0);
+        controls.fade.fade_to(1.0, remaining, curve);
         sink.append(source);
+
+        if let (Some(old_sink), Some(old_controls)) = (self.sink.take(), self.controls.take()) {
+            old_controls.fade.fade_to(0.0, remaining, curve);
+            self.outgoing = Some(OutgoingTrack {
+                sink: old_sink,
+                controls: old_controls,
+            });
+        }
+
+        self.announce_finished();
+        self.queue.follow(index);
         self.sink = Some(sink);
+        self.controls = Some(controls);
+        self.apply_volume();
+        self.music_file = track;
+        self.music_file.set_state(MusicState::Playing);
+        self.total_duration = total_duration;
+        self.preload_attempted = false;
+        self.ab_loop = AbLoop::default();
+        self.announce_track();
+    }
 
-        Ok(())
+    /// Drops the faded-out sink once it has played to the end.
+    fn retire_outgoing(&mut self) {
+        if self.outgoing.as_ref().is_some_and(|o| o.sink.empty()) {
+            self.outgoing = None;
+        }
+    }
+
+    // --- VOLUME ---
+
+    pub fn volume(&self) -> Volume {
+        self.volume
+    }
+
+    /// Restores a whole volume state, e.g. the one saved on the last run.
+    pub fn set_volume_state(&mut self, volume: Volume) {
+        self.volume = volume;
+        self.apply_volume();
+    }
+
+    /// Sets the slider level (0.0 ..= 1.0); the audible gain follows [`Volume::gain`].
+    pub fn set_volume(&mut self, level: f32) {
+        self.volume.set_level(level);
+        self.apply_volume();
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.volume.set_muted(muted);
+        self.apply_volume();
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.volume.toggle_mute();
+        self.apply_volume();
     }
-    pub fn resume(&self) {
+
+    fn apply_volume(&self) {
+        let gain = self.volume.gain() * self.sleep_level;
         if let Some(sink) = &self.sink {
-            sink.play();
+            sink.set_volume(gain);
+        }
+        if let Some(outgoing) = &self.outgoing {
+            outgoing.sink.set_volume(gain);
         }
     }
-    pub fn stop(&self) {
-        if let Some(sink) = &self.sink {
-            sink.stop();
+
+    // --- REPLAYGAIN ---
+
+    pub fn replay_gain(&self) -> ReplayGainSettings {
+        self.replay_gain
+    }
+
+    /// Changes the mode/pre-amp and re-levels everything currently playing.
+    pub fn set_replay_gain(&mut self, settings: ReplayGainSettings) {
+        self.replay_gain = settings;
+
+        let playing = self
+            .controls
+            .iter()
+            .chain(self.preloaded.iter().map(|p| &p.controls))
+            .chain(self.outgoing.iter().map(|o| &o.controls));
+        for controls in playing {
+            controls
+                .replay_gain
+                .set(settings.factor(&controls.tagged_gain));
         }
     }
-    pub fn pause(&self) {
-        if let Some(sink) = &self.sink {
-            sink.pause();
+
+    // --- EQUALIZER ---
+
+    pub fn equalizer_params(&self) -> EqParams {
+        self.equalizer.params()
+    }
+
+    /// Takes effect on the playing track within a few milliseconds.
+    pub fn set_equalizer_params(&mut self, params: EqParams) {
+        self.update_equalizer(|state| state.params = params);
+    }
+
+    /// Built-in presets followed by the user's own.
+    pub fn eq_presets(&self) -> impl Iterator<Item = EqPreset> + '_ {
+        EqPreset::builtin()
+            .into_iter()
+            .chain(self.equalizer_state.user_presets.iter().cloned())
+    }
+
+    pub fn user_eq_presets(&self) -> &[EqPreset] {
+        &self.equalizer_state.user_presets
+    }
+
+    pub fn apply_eq_preset(&mut self, preset: &EqPreset) {
+        self.update_equalizer(|state| {
+            preset.apply_to(&mut state.params);
+            state.params.enabled = true;
+        });
+    }
+
+    /// Saves the current band gains under `name`, replacing a user preset of the same name.
+    pub fn save_eq_preset(&mut self, name: &str) {
+        let preset = EqPreset::from_params(name, &self.equalizer_state.params);
+        let presets = &mut Arc::make_mut(&mut self.equalizer_state).user_presets;
+        match presets.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+    }
+
+    pub fn delete_eq_preset(&mut self, name: &str) {
+        Arc::make_mut(&mut self.equalizer_state)
+            .user_presets
+            .retain(|p| p.name != name);
+    }
+
+    pub fn equalizer_state(&self) -> &Arc<EqualizerState> {
+        &self.equalizer_state
+    }
+
+    pub fn set_equalizer_state(&mut self, state: EqualizerState) {
+        self.equalizer.set_params(state.params.clone());
+        self.equalizer_state = Arc::new(state);
+    }
+
+    /// Edits the state and hands the resulting params to the audio chain.
+    fn update_equalizer(&mut self, edit: impl FnOnce(&mut EqualizerState)) {
+        let state = Arc::make_mut(&mut self.equalizer_state);
+        edit(state);
+        self.equalizer.set_params(state.params.clone());
+    }
+
+    // --- CROSSFADE ---
+
+    pub fn crossfade(&self) -> CrossfadeSettings {
+        self.crossfade
+    }
+
+    pub fn set_crossfade(&mut self, settings: CrossfadeSettings) {
+        self.crossfade = CrossfadeSettings {
+            duration: settings.duration.min(CrossfadeSettings::MAX_DURATION),
+            ..settings
+        };
+    }
+
+    /// Refreshes the loaded and preloaded tracks after `path`'s tags were rewritten.
+    pub fn update_tags(&mut self, path: &Path, tags: TrackTags) {
+        if self.music_file.path() == path {
+            self.music_file.set_tags(tags.clone());
+        }
+        if let Some(preloaded) = self.preloaded.as_mut()
+            && preloaded.track.path() == path
+        {
+            preloaded.track.set_tags(tags);
         }
     }
 
@@ -80,19 +621,467 @@ impl MusicService {
         self.sink.is_some()
     }
 
-    pub fn set_pos(&self, pos: Duration) {
-        if let Some(sink) = self.sink.as_ref() {
-            if let Err(e) = sink.try_seek(pos) {
-                eprintln!("Error seeking to {:?}: {:?}", pos, e);
-            }
+    /// Seeks without blocking; the audio thread ramps out, jumps and ramps back in.
+    pub fn set_pos(&mut self, pos: Duration) {
+        if let Some(controls) = self.controls.as_ref() {
+            controls.transport.seek(pos);
+            controls.position.set(pos);
+            self.last_tick = Some(pos);
+            self.events.emit(PlaybackEvent::Seeked(pos));
         }
     }
+    /// Position in the recording (source time), so it matches the track length
+    /// at any playback speed.
     pub fn get_pos(&self) -> Option<Duration> {
-        let pos = self.sink.as_ref()?.get_pos();
-        Some(pos)
+        self.sink.as_ref()?;
+        if self.music_file.state() == MusicState::Stopped {
+            return Some(Duration::ZERO);
+        }
+        let controls = self.controls.as_ref()?;
+        // The old position keeps moving while a seek ramps out.
+        Some(
+            controls
+                .transport
+                .pending_seek()
+                .unwrap_or_else(|| controls.position.get()),
+        )
+    }
+
+    /// Jumps `by` past the current position, stopping at the end of the track.
+    pub fn seek_forward(&mut self, by: Duration) {
+        let Some(pos) = self.get_pos() else { return };
+        let target = pos.saturating_add(by);
+        self.set_pos(self.total_duration.map_or(target, |total| target.min(total)));
+    }
+
+    /// Jumps `by` before the current position, stopping at the start.
+    pub fn seek_backward(&mut self, by: Duration) {
+        if let Some(pos) = self.get_pos() {
+            self.set_pos(pos.saturating_sub(by));
+        }
+    }
+
+    /// Wall-clock time until the current track ends at the current speed.
+    fn remaining_playback_time(&self) -> Option<Duration> {
+        let remaining = self.total_duration?.saturating_sub(self.get_pos()?);
+        Some(remaining.div_f32(self.speed.get()))
+    }
+
+    // --- A-B LOOP ---
+
+    pub fn ab_loop(&self) -> AbLoop {
+        self.ab_loop
+    }
+
+    pub fn set_ab_loop(&mut self, ab_loop: AbLoop) {
+        self.ab_loop = ab_loop;
+    }
+
+    /// Marks the current position as the start of the loop.
+    pub fn set_loop_a(&mut self) {
+        if let Some(pos) = self.get_pos() {
+            self.ab_loop.set_a(pos);
+        }
+    }
+
+    /// Marks the current position as the end of the loop.
+    pub fn set_loop_b(&mut self) {
+        if let Some(pos) = self.get_pos() {
+            self.ab_loop.set_b(pos);
+        }
+    }
+
+    pub fn clear_ab_loop(&mut self) {
+        self.ab_loop = AbLoop::default();
+    }
+
+    fn enforce_ab_loop(&mut self) {
+        let Some((a, b)) = self.ab_loop.region() else { return };
+        if self.music_file.state() == MusicState::Playing
+            && self.get_pos().is_some_and(|pos| pos >= b)
+        {
+            self.set_pos(a);
+        }
+    }
+
+    // --- SPEED ---
+
+    pub fn speed(&self) -> f32 {
+        self.speed.get()
+    }
+
+    /// Changes tempo without changing pitch; clamped to [`SPEED_RANGE`](crate::audio::stretch::SPEED_RANGE).
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed.set(speed);
     }
 
     pub fn get_total_duration(&self) -> Option<Duration> {
         self.total_duration
     }
+
+    // --- RESUME POINTS ---
+
+    pub fn resume_points(&self) -> &Arc<ResumePoints> {
+        &self.resume_points
+    }
+
+    /// Restores the positions saved on the last run.
+    pub fn set_resume_points(&mut self, resume_points: ResumePoints) {
+        self.resume_points = Arc::new(resume_points);
+    }
+
+    pub fn resume_settings(&self) -> ResumeSettings {
+        self.resume_points.settings()
+    }
+
+    pub fn set_resume_settings(&mut self, settings: ResumeSettings) {
+        Arc::make_mut(&mut self.resume_points).set_settings(settings);
+        if !settings.enabled {
+            self.resume_offer = None;
+        }
+    }
+
+    /// Where the current track was left off last time, if that's worth offering.
+    pub fn resume_offer(&self) -> Option<Duration> {
+        self.resume_offer
+    }
+
+    pub fn accept_resume_offer(&mut self) {
+        if let Some(pos) = self.resume_offer.take() {
+            self.set_pos(pos);
+        }
+    }
+
+    pub fn dismiss_resume_offer(&mut self) {
+        self.resume_offer = None;
+    }
+
+    /// Saves the current track's position, e.g. before the app exits. Stopped and
+    /// finished tracks have nothing to save.
+    pub fn remember_position(&mut self) {
+        if matches!(self.state(), MusicState::Playing | MusicState::Paused)
+            && let Some(pos) = self.get_pos()
+        {
+            Arc::make_mut(&mut self.resume_points).remember(
+                self.music_file.path(),
+                pos,
+                self.total_duration,
+            );
+        }
+    }
+
+    // --- TRANSPORT RAMPS ---
+
+    pub fn transport_ramp(&self) -> Duration {
+        self.transport_ramp
+    }
+
+    /// Sets how long pause, resume, stop and seek take to ramp; zero acts instantly.
+    pub fn set_transport_ramp(&mut self, ramp: Duration) {
+        self.transport_ramp = ramp.min(transport::MAX_RAMP);
+        for handle in self.transport_handles() {
+            handle.set_ramp(self.transport_ramp);
+        }
+    }
+
+    fn transport_handles(&self) -> impl Iterator<Item = &TransportHandle> {
+        let current = self.controls.as_ref();
+        let outgoing = self.outgoing.as_ref().map(|o| &o.controls);
+        let preloaded = self.preloaded.as_ref().map(|p| &p.controls);
+        [current, outgoing, preloaded]
+            .into_iter()
+            .flatten()
+            .map(|controls| &controls.transport)
+    }
+
+    // --- SLEEP TIMER ---
+
+    pub fn sleep_timer(&self) -> Option<SleepTimer> {
+        self.sleep_timer
+    }
+
+    /// Starts (or restarts) the countdown to pausing playback.
+    pub fn set_sleep_timer(&mut self, mode: SleepMode) {
+        self.sleep_timer = Some(SleepTimer::new(mode, self.clock.now()));
+        self.set_sleep_level(1.0);
+    }
+
+    pub fn extend_sleep_timer(&mut self) {
+        if let Some(timer) = &mut self.sleep_timer {
+            timer.extend();
+        }
+        self.set_sleep_level(1.0);
+    }
+
+    pub fn cancel_sleep_timer(&mut self) {
+        self.sleep_timer = None;
+        self.set_sleep_level(1.0);
+    }
+
+    /// Time until the sleep timer pauses playback, when it can be known.
+    pub fn sleep_timer_remaining(&self) -> Option<Duration> {
+        self.sleep_timer?
+            .remaining(self.remaining_playback_time(), self.clock.now())
+    }
+
+    /// Replaces the clock the sleep timer counts down on, e.g. with [`Clock::manual`] in tests.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    fn run_sleep_timer(&mut self) {
+        let track_remaining = self.remaining_playback_time();
+        let now = self.clock.now();
+        let Some(timer) = &mut self.sleep_timer else { return };
+        if timer.is_due(now) {
+            self.pause();
+            self.cancel_sleep_timer();
+            return;
+        }
+        let level = timer.level(track_remaining, now);
+        self.set_sleep_level(level);
+    }
+
+    fn set_sleep_level(&mut self, level: f32) {
+        if level != self.sleep_level {
+            self.sleep_level = level;
+            self.apply_volume();
+        }
+    }
+
+    // --- EVENTS ---
+
+    /// Receives every event from now on. Drop the receiver to unsubscribe.
+    pub fn subscribe(&mut self) -> Receiver<PlaybackEvent> {
+        self.events.subscribe()
+    }
+
+    /// Like [`MusicService::subscribe`], with a channel made by the caller.
+    pub fn add_subscriber(&mut self, sender: Sender<PlaybackEvent>) {
+        self.events.add_subscriber(sender);
+    }
+
+    fn announce_track(&mut self) {
+        self.last_tick = None;
+        self.resume_offer = self
+            .resume_points
+            .position(self.music_file.path(), self.total_duration);
+        self.events.emit(PlaybackEvent::TrackLoaded {
+            path: self.music_file.path().to_path_buf(),
+            index: self.queue.current_index(),
+            duration: self.total_duration,
+        });
+        self.events.emit(PlaybackEvent::Playing);
+    }
+
+    fn announce_finished(&mut self) {
+        Arc::make_mut(&mut self.resume_points).forget(self.music_file.path());
+        if let Some(timer) = &mut self.sleep_timer {
+            timer.track_finished();
+        }
+        self.events.emit(PlaybackEvent::TrackFinished {
+            path: self.music_file.path().to_path_buf(),
+        });
+    }
+
+    fn tick_position(&mut self) {
+        if self.music_file.state() != MusicState::Playing {
+            return;
+        }
+        let Some(pos) = self.get_pos() else { return };
+        let due = self
+            .last_tick
+            .is_none_or(|last| pos.abs_diff(last) >= EventBus::TICK_INTERVAL);
+        if due {
+            self.last_tick = Some(pos);
+            self.events.emit(PlaybackEvent::PositionTick(pos));
+        }
+    }
+
+    fn report(&mut self, error: MusicOpenError) {
+        self.events.emit(PlaybackEvent::Error(error.clone()));
+        self.last_error = Some(error);
+    }
+
+    // --- ERRORS ---
+
+    /// The most recent failure to open a track or the output, including ones the
+    /// queue skipped past on its own.
+    pub fn last_error(&self) -> Option<&MusicOpenError> {
+        self.last_error.as_ref()
+    }
+
+    pub fn clear_error(&mut self) {
+        self.last_error = None;
+    }
+
+    // --- OUTPUT DEVICE ---
+
+    /// The device chosen by the user; `None` follows the system default.
+    pub fn output_device(&self) -> Option<&str> {
+        self.output.preferred()
+    }
+
+    /// The device actually playing, which differs from the chosen one after a fallback.
+    pub fn active_output_device(&self) -> Option<&str> {
+        self.output.active()
+    }
+
+    pub fn set_output_device(&mut self, name: Option<String>) {
+        if self.output.preferred() == name.as_deref() {
+            return;
+        }
+        self.output.set_preferred(name);
+        self.reopen_output();
+    }
+
+    /// False while playing into the silent fallback because no device could be opened.
+    pub fn is_audio_available(&self) -> bool {
+        self.backend.is_audible()
+    }
+
+    /// Rebuilds the output stream and picks the current track back up where it was.
+    fn reopen_output(&mut self) {
+        let backend: Box<dyn OutputBackend> = match self.output.open_stream() {
+            Ok(stream) => Box::new(stream),
+            // Still nothing to play on; the silent clock keeps going.
+            Err(_) if !self.backend.is_audible() => return,
+            Err(e) => {
+                eprintln!("Audio output unavailable, continuing silently: {:?}", e);
+                let path = self.is_music_loaded().then(|| self.music_file.path());
+                self.report(MusicOpenError::output(path, e));
+                Box::new(NullBackend::default())
+            }
+        };
+        self.move_to(backend);
+    }
+
+    /// Moves playback onto `backend`, e.g. another
+    /// [`OfflineBackend`](crate::audio::backend::OfflineBackend) in tests, the way
+    /// switching devices does.
+    pub fn set_backend(&mut self, backend: impl OutputBackend + 'static) {
+        self.move_to(Box::new(backend));
+    }
+
+    fn move_to(&mut self, backend: Box<dyn OutputBackend>) {
+        let state = self.state();
+        let pos = self.get_pos().unwrap_or_default();
+
+        // Sinks are tied to the old stream's mixer; release them before it goes away.
+        self.outgoing = None;
+        self.preloaded = None;
+        self.preload_attempted = false;
+        self.controls = None;
+        self.sink = None;
+        self.backend = backend;
+
+        if !matches!(state, MusicState::Playing | MusicState::Paused) {
+            return;
+        }
+        let path = self.music_file.path().to_path_buf();
+        let decoder = match Self::decode(&path) {
+            Ok((_, decoder)) => decoder,
+            Err(e) => {
+                eprintln!("Failed to resume {:?} on the new device: {}", path, e);
+                self.report(e);
+                return;
+            }
+        };
+        // The same track carries on where it was, so this isn't a new load: no
+        // events, no resume offer, and the saved resume point stays as it is.
+        let (source, controls) = self.build_source(&self.music_file, decoder, 1.0);
+        if state == MusicState::Paused {
+            controls.transport.pause();
+        }
+        controls.transport.seek(pos);
+        controls.position.set(pos);
+        let sink = Sink::connect_new(self.backend.mixer());
+        sink.append(source);
+        self.sink = Some(sink);
+        self.controls = Some(controls);
+        self.last_tick = Some(pos);
+        self.apply_volume();
+    }
+
+    // --- QUEUE ---
+
+    pub fn queue(&self) -> &PlayQueue {
+        &self.queue
+    }
+
+    pub fn current_index(&self) -> Option<usize> {
+        self.queue.current_index()
+    }
+
+    /// Replaces the queue with `tracks` and starts playing the one at `index`.
+    pub fn play_tracks(&mut self, tracks: Vec<PathBuf>, index: usize) -> Result<(), MusicOpenError> {
+        self.queue.set_tracks(tracks);
+        self.play_index(index)
+    }
+
+    pub fn enqueue(&mut self, path: impl Into<PathBuf>) {
+        self.queue.enqueue(path);
+    }
+
+    pub fn insert_next(&mut self, path: impl Into<PathBuf>) {
+        self.queue.insert_next(path);
+    }
+
+    pub fn remove_from_queue(&mut self, index: usize) -> Option<PathBuf> {
+        self.queue.remove(index)
+    }
+
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+    }
+
+    pub fn playback_mode(&self) -> PlaybackMode {
+        self.queue.mode()
+    }
+
+    pub fn set_playback_mode(&mut self, mode: PlaybackMode) {
+        self.queue.set_mode(mode);
+    }
+
+    pub fn toggle_shuffle(&mut self) {
+        let shuffle = !self.queue.mode().shuffle;
+        self.queue.set_shuffle(shuffle);
+    }
+
+    pub fn cycle_repeat(&mut self) {
+        let repeat = self.queue.mode().repeat.cycle();
+        self.queue.set_repeat(repeat);
+    }
+
+    pub fn play_index(&mut self, index: usize) -> Result<(), MusicOpenError> {
+        let path = self
+            .queue
+            .jump_to(index)
+            .map(Path::to_path_buf)
+            .ok_or_else(|| MusicOpenError::new(MusicOpenErrorKind::NotInQueue, None))?;
+        self.open(path)
+    }
+
+    /// Advances to the next queued track. Returns `Ok(false)` at the end of the queue.
+    pub fn play_next(&mut self) -> Result<bool, MusicOpenError> {
+        match self.queue.advance().map(Path::to_path_buf) {
+            Some(path) => self.open(path).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Restarts the current track if it has played for a few seconds, otherwise
+    /// steps back to the previous one.
+    pub fn play_previous(&mut self) -> Result<bool, MusicOpenError> {
+        const RESTART_THRESHOLD: Duration = Duration::from_secs(3);
+
+        if self.get_pos().is_some_and(|pos| pos > RESTART_THRESHOLD) {
+            self.set_pos(Duration::ZERO);
+            return Ok(true);
+        }
+        match self.queue.step_back().map(Path::to_path_buf) {
+            Some(path) => self.open(path).map(|_| true),
+            None => Ok(false),
+        }
+    }
 }
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+/// Section of the current track to repeat, from `a` to `b`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AbLoop {
+    pub a: Option<Duration>,
+    pub b: Option<Duration>,
+}
+
+impl AbLoop {
+    /// Both points set and in order.
+    pub fn region(&self) -> Option<(Duration, Duration)> {
+        match (self.a, self.b) {
+            (Some(a), Some(b)) if a < b => Some((a, b)),
+            _ => None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.region().is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.a.is_none() && self.b.is_none()
+    }
+
+    /// Sets A, dropping a B that would now come before it.
+    pub fn set_a(&mut self, pos: Duration) {
+        self.a = Some(pos);
+        if self.b.is_some_and(|b| b <= pos) {
+            self.b = None;
+        }
+    }
+
+    /// Sets B; without an A the loop starts from the beginning of the track.
+    pub fn set_b(&mut self, pos: Duration) {
+        let a = *self.a.get_or_insert(Duration::ZERO);
+        if pos > a {
+            self.b = Some(pos);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::audio::{
+    fade::FadeHandle, gain::GainHandle, stretch::PositionHandle, transport::TransportHandle,
+};
+use crate::models::replay_gain::ReplayGain;
+
+/// Live handles into the processing chain built for one decoded track.
+pub struct SourceControls {
+    pub fade: FadeHandle,
+    pub replay_gain: GainHandle,
+    /// Tagged values the ReplayGain factor is recomputed from when settings change.
+    pub tagged_gain: ReplayGain,
+    /// Position in the recording, independent of playback speed.
+    pub position: PositionHandle,
+    /// Pause, stop and seek, ramped so none of them click.
+    pub transport: TransportHandle,
+}
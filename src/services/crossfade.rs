@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use rodio::Sink;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::fade::FadeCurve;
+use crate::services::controls::SourceControls;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrossfadeSettings {
+    /// Overlap between the outgoing and incoming track; zero means gapless instead.
+    pub duration: Duration,
+    pub curve: FadeCurve,
+}
+
+impl CrossfadeSettings {
+    pub const MAX_DURATION: Duration = Duration::from_secs(12);
+
+    pub fn is_enabled(&self) -> bool {
+        !self.duration.is_zero()
+    }
+}
+
+/// The previous track, still fading out on its own sink while the next one fades in.
+pub struct OutgoingTrack {
+    pub sink: Sink,
+    pub controls: SourceControls,
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::equalizer::{BAND_FREQUENCIES, EqParams};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EqPreset {
+    pub name: String,
+    /// Gain in dB for each of [`BAND_FREQUENCIES`].
+    pub gains: Vec<f32>,
+}
+
+impl EqPreset {
+    pub fn new(name: impl Into<String>, gains: [f32; BAND_FREQUENCIES.len()]) -> Self {
+        Self {
+            name: name.into(),
+            gains: gains.to_vec(),
+        }
+    }
+
+    pub fn from_params(name: impl Into<String>, params: &EqParams) -> Self {
+        Self {
+            name: name.into(),
+            gains: params.bands.iter().map(|band| band.gain_db).collect(),
+        }
+    }
+
+    /// Applies the preset's gains to `params`, keeping band frequencies and Q.
+    pub fn apply_to(&self, params: &mut EqParams) {
+        for (band, gain) in params.bands.iter_mut().zip(&self.gains) {
+            band.gain_db = *gain;
+        }
+    }
+
+    pub fn builtin() -> Vec<EqPreset> {
+        vec![
+            Self::new("Flat", [0.0; 10]),
+            Self::new("Bass Boost", [6.0, 5.0, 4.0, 2.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0]),
+            Self::new("Treble Boost", [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.5, 4.0, 5.0, 6.0]),
+            Self::new("Vocal", [-2.0, -2.0, -1.0, 1.0, 3.0, 4.0, 3.0, 1.5, 0.0, -1.0]),
+            Self::new("Rock", [4.0, 3.0, 2.0, 0.0, -1.0, -1.0, 1.0, 2.5, 3.5, 4.0]),
+            Self::new("Classical", [3.0, 2.0, 1.0, 0.0, 0.0, 0.0, -1.0, -1.0, 1.0, 2.0]),
+            Self::new("Electronic", [5.0, 4.0, 1.5, 0.0, -2.0, 1.0, 0.0, 1.5, 4.0, 5.0]),
+            Self::new("Loudness", [5.0, 3.5, 0.0, 0.0, -1.0, 0.0, -1.0, 0.0, 3.0, 4.0]),
+        ]
+    }
+}
+
+/// Everything about the equalizer that is remembered between runs.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EqualizerState {
+    pub params: EqParams,
+    pub user_presets: Vec<EqPreset>,
+}
@@ -0,0 +1,116 @@
+use std::{
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rodio::decoder::DecoderError;
+
+/// What went wrong, coarse enough to pick a message for the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MusicOpenErrorKind {
+    FileMissing,
+    PermissionDenied,
+    /// Any other I/O failure while reading the file.
+    Unreadable,
+    /// Not an audio format or codec the decoder knows.
+    UnsupportedFormat,
+    /// Recognised, but the data is malformed.
+    CorruptStream,
+    /// The audio output could not be opened or stopped working.
+    OutputFailure,
+    /// Asked to play a queue position that doesn't exist.
+    NotInQueue,
+}
+
+impl MusicOpenErrorKind {
+    pub fn description(self) -> &'static str {
+        match self {
+            MusicOpenErrorKind::FileMissing => "file not found",
+            MusicOpenErrorKind::PermissionDenied => "permission denied",
+            MusicOpenErrorKind::Unreadable => "could not read the file",
+            MusicOpenErrorKind::UnsupportedFormat => "unsupported format or codec",
+            MusicOpenErrorKind::CorruptStream => "the audio data is corrupt",
+            MusicOpenErrorKind::OutputFailure => "audio output failed",
+            MusicOpenErrorKind::NotInQueue => "no such track in the queue",
+        }
+    }
+}
+
+/// Failure to open, decode or play a track, with the file and underlying cause.
+#[derive(Clone, Debug)]
+pub struct MusicOpenError {
+    kind: MusicOpenErrorKind,
+    path: Option<PathBuf>,
+    source: Option<Arc<dyn Error + Send + Sync>>,
+}
+
+impl MusicOpenError {
+    pub fn new(kind: MusicOpenErrorKind, path: Option<&Path>) -> Self {
+        Self {
+            kind,
+            path: path.map(Path::to_path_buf),
+            source: None,
+        }
+    }
+
+    fn with_source(mut self, source: impl Error + Send + Sync + 'static) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    pub fn io(path: &Path, error: io::Error) -> Self {
+        let kind = match error.kind() {
+            io::ErrorKind::NotFound => MusicOpenErrorKind::FileMissing,
+            io::ErrorKind::PermissionDenied => MusicOpenErrorKind::PermissionDenied,
+            _ => MusicOpenErrorKind::Unreadable,
+        };
+        Self::new(kind, Some(path)).with_source(error)
+    }
+
+    pub fn decoder(path: &Path, error: DecoderError) -> Self {
+        let kind = match error {
+            DecoderError::UnrecognizedFormat | DecoderError::NoStreams => {
+                MusicOpenErrorKind::UnsupportedFormat
+            }
+            DecoderError::IoError(_) => MusicOpenErrorKind::Unreadable,
+            DecoderError::DecodeError(_)
+            | DecoderError::LimitError(_)
+            | DecoderError::ResetRequired => MusicOpenErrorKind::CorruptStream,
+        };
+        Self::new(kind, Some(path)).with_source(error)
+    }
+
+    pub fn output(path: Option<&Path>, error: impl Error + Send + Sync + 'static) -> Self {
+        Self::new(MusicOpenErrorKind::OutputFailure, path).with_source(error)
+    }
+
+    pub fn kind(&self) -> MusicOpenErrorKind {
+        self.kind
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+impl fmt::Display for MusicOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .path
+            .as_deref()
+            .and_then(Path::file_name)
+            .filter(|name| !name.is_empty());
+        match name {
+            Some(name) => write!(f, "{}: {}", name.to_string_lossy(), self.kind.description()),
+            None => f.write_str(self.kind.description()),
+        }
+    }
+}
+
+impl Error for MusicOpenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|e| e as &(dyn Error + 'static))
+    }
+}
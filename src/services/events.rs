@@ -0,0 +1,56 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    time::Duration,
+};
+
+use crate::services::MusicOpenError;
+
+/// State changes published by `MusicService` to anyone who subscribed.
+#[derive(Clone, Debug)]
+pub enum PlaybackEvent {
+    /// A track was decoded and is now the current one.
+    TrackLoaded {
+        path: PathBuf,
+        index: Option<usize>,
+        duration: Option<Duration>,
+    },
+    Playing,
+    Paused,
+    Stopped,
+    Seeked(Duration),
+    /// Emitted from `update` while playing, at most every [`EventBus::TICK_INTERVAL`].
+    PositionTick(Duration),
+    /// The track played to its end (or handed over to a crossfade).
+    TrackFinished { path: PathBuf },
+    Error(MusicOpenError),
+}
+
+/// Fans events out to every live subscriber; dropped receivers are forgotten.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Vec<Sender<PlaybackEvent>>,
+}
+
+impl EventBus {
+    pub const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&mut self) -> Receiver<PlaybackEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.add_subscriber(sender);
+        receiver
+    }
+
+    pub fn add_subscriber(&mut self, sender: Sender<PlaybackEvent>) {
+        self.subscribers.push(sender);
+    }
+
+    pub fn emit(&mut self, event: PlaybackEvent) {
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
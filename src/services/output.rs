@@ -0,0 +1,140 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use rodio::{
+    OutputStream, OutputStreamBuilder, StreamError,
+    cpal::{self, traits::HostTrait},
+    DeviceTrait,
+};
+
+/// How often to look for a missing preferred device, or any device at all.
+const PROBE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Names of the output devices the default host currently offers.
+pub fn output_device_names() -> Vec<String> {
+    let host = cpal::default_host();
+    match host.output_devices() {
+        Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+        Err(e) => {
+            eprintln!("Failed to list output devices: {:?}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Which device the user asked for and which one is actually playing.
+pub struct OutputDevice {
+    /// `None` follows the system default.
+    preferred: Option<String>,
+    active: Option<String>,
+    /// Raised from the audio thread when the stream reports the device is gone.
+    lost: Arc<AtomicBool>,
+    last_probe: Instant,
+}
+
+impl Default for OutputDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputDevice {
+    pub fn new() -> Self {
+        Self {
+            preferred: None,
+            active: None,
+            lost: Arc::new(AtomicBool::new(false)),
+            last_probe: Instant::now(),
+        }
+    }
+
+    pub fn preferred(&self) -> Option<&str> {
+        self.preferred.as_deref()
+    }
+
+    pub fn set_preferred(&mut self, name: Option<String>) {
+        self.preferred = name;
+    }
+
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Opens the preferred device, falling back to the system default when it is
+    /// missing or refuses to open.
+    pub fn open_stream(&mut self) -> Result<OutputStream, StreamError> {
+        self.lost.store(false, Ordering::Relaxed);
+        self.last_probe = Instant::now();
+        self.active = None;
+
+        if let Some(name) = &self.preferred {
+            match find_device(name).map(|device| self.open_device(device)) {
+                Some(Ok(stream)) => {
+                    self.active = Some(name.clone());
+                    return Ok(stream);
+                }
+                Some(Err(e)) => eprintln!("Failed to open {:?}, using default: {:?}", name, e),
+                None => eprintln!("Output device {:?} not found, using default", name),
+            }
+        }
+
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(StreamError::NoDevice)?;
+        let name = device.name().ok();
+        let stream = self.open_device(device)?;
+        self.active = name;
+        Ok(stream)
+    }
+
+    fn open_device(&self, device: cpal::Device) -> Result<OutputStream, StreamError> {
+        let lost = self.lost.clone();
+        let mut stream = OutputStreamBuilder::from_device(device)?
+            .with_error_callback(move |e| {
+                eprintln!("Audio output error: {:?}", e);
+                if matches!(e, cpal::StreamError::DeviceNotAvailable) {
+                    lost.store(true, Ordering::Relaxed);
+                }
+            })
+            .open_stream_or_fallback()?;
+        // Swapping devices drops streams routinely; rodio's drop message is just noise.
+        stream.log_on_drop(false);
+        Ok(stream)
+    }
+
+    /// Whether the stream should be rebuilt: the device vanished, the preferred one
+    /// is back after a fallback, or it's time to retry after opening nothing at all.
+    /// Cheap to call every frame.
+    pub fn needs_reopen(&mut self, audible: bool) -> bool {
+        if self.lost.load(Ordering::Relaxed) {
+            return true;
+        }
+        if self.last_probe.elapsed() < PROBE_INTERVAL {
+            return false;
+        }
+        if !audible {
+            self.last_probe = Instant::now();
+            return true;
+        }
+        let Some(preferred) = &self.preferred else {
+            return false;
+        };
+        if self.active.as_ref() == Some(preferred) {
+            return false;
+        }
+        self.last_probe = Instant::now();
+        find_device(preferred).is_some()
+    }
+}
+
+fn find_device(name: &str) -> Option<cpal::Device> {
+    cpal::default_host()
+        .output_devices()
+        .ok()?
+        .find(|device| device.name().is_ok_and(|n| n == name))
+}
@@ -0,0 +1,556 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::audio::equalizer::EqParams;
+use crate::models::{metadata::TrackMetadata, tags::TrackTags, track::MusicState};
+use crate::services::{
+    MusicOpenError, MusicService,
+    ab_loop::AbLoop,
+    crossfade::CrossfadeSettings,
+    equalizer::{EqPreset, EqualizerState},
+    events::PlaybackEvent,
+    queue::PlaybackMode,
+    replay_gain::ReplayGainSettings,
+    resume_points::{ResumePoints, ResumeSettings},
+    sleep_timer::{SleepMode, SleepTimer},
+    volume::Volume,
+};
+
+type Command = Box<dyn FnOnce(&mut MusicService) + Send>;
+type Preview = Box<dyn Fn(&mut PlayerView)>;
+
+/// How long the audio thread waits for a command before running `update` anyway.
+const TICK: Duration = Duration::from_millis(10);
+
+/// What the UI needs to draw, copied out of the service by the audio thread.
+#[derive(Clone, Debug)]
+pub struct PlayerView {
+    pub state: MusicState,
+    pub music_loaded: bool,
+    pub track_name: String,
+    pub metadata: TrackMetadata,
+    pub current_path: Option<PathBuf>,
+    pub current_index: Option<usize>,
+    pub queue_len: usize,
+    pub volume: Volume,
+    pub speed: f32,
+    pub transport_ramp: Duration,
+    pub crossfade: CrossfadeSettings,
+    pub replay_gain: ReplayGainSettings,
+    pub ab_loop: AbLoop,
+    pub playback_mode: PlaybackMode,
+    pub equalizer: Arc<EqualizerState>,
+    pub output_device: Option<String>,
+    pub active_output_device: Option<String>,
+    pub audio_available: bool,
+    pub last_error: Option<MusicOpenError>,
+    pub resume_settings: ResumeSettings,
+    /// Saved positions, for persisting without asking the audio thread.
+    pub resume_points: Arc<ResumePoints>,
+    pub resume_offer: Option<Duration>,
+    pub sleep_timer: Option<SleepTimer>,
+    pub sleep_timer_remaining: Option<Duration>,
+    /// Number of commands the service had run when this was taken.
+    applied: u64,
+}
+
+impl PlayerView {
+    fn of(service: &MusicService, applied: u64) -> Self {
+        let music_loaded = service.is_music_loaded();
+        Self {
+            state: service.state(),
+            music_loaded,
+            track_name: if music_loaded {
+                service.music_file.name()
+            } else {
+                String::new()
+            },
+            metadata: service.music_file.metadata().clone(),
+            current_path: service.queue().current().map(PathBuf::from),
+            current_index: service.current_index(),
+            queue_len: service.queue().len(),
+            volume: service.volume(),
+            speed: service.speed(),
+            transport_ramp: service.transport_ramp(),
+            crossfade: service.crossfade(),
+            replay_gain: service.replay_gain(),
+            ab_loop: service.ab_loop(),
+            playback_mode: service.playback_mode(),
+            equalizer: service.equalizer_state().clone(),
+            output_device: service.output_device().map(str::to_owned),
+            active_output_device: service.active_output_device().map(str::to_owned),
+            audio_available: service.is_audio_available(),
+            last_error: service.last_error().cloned(),
+            resume_settings: service.resume_settings(),
+            resume_points: service.resume_points().clone(),
+            resume_offer: service.resume_offer(),
+            sleep_timer: service.sleep_timer(),
+            sleep_timer_remaining: service.sleep_timer_remaining(),
+            applied,
+        }
+    }
+
+    /// Built-in presets followed by the user's own.
+    pub fn eq_presets(&self) -> impl Iterator<Item = EqPreset> + '_ {
+        EqPreset::builtin()
+            .into_iter()
+            .chain(self.equalizer.user_presets.iter().cloned())
+    }
+}
+
+struct LoadFinished {
+    id: u64,
+    result: Result<(), MusicOpenError>,
+}
+
+/// Runs a `MusicService` on its own thread and talks to it over channels.
+///
+/// File opening, tag parsing and decoder probing happen there, so the UI never
+/// waits on the disk. Setters return immediately; getters read the latest
+/// [`PlayerView`], refreshed by [`Player::poll`].
+pub struct Player {
+    commands: Option<Sender<Command>>,
+    shared: Arc<Mutex<PlayerView>>,
+    view: PlayerView,
+    /// Commands sent so far, compared against `PlayerView::applied`.
+    sent: u64,
+    /// Changes the UI already shows, each with the number of the command that makes
+    /// it for real. Laid over every snapshot until the audio thread has run that command.
+    previews: Vec<(u64, Preview)>,
+    loads: (Sender<LoadFinished>, Receiver<LoadFinished>),
+    load_id: u64,
+    loading: Option<PathBuf>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Player {
+    pub fn new() -> Self {
+        Self::spawn(MusicService::new)
+    }
+
+    /// Starts the audio thread with the service built by `make_service`. The service
+    /// is created on that thread because output streams can't move between threads.
+    pub fn spawn(make_service: impl FnOnce() -> MusicService + Send + 'static) -> Self {
+        let (commands, receiver) = mpsc::channel::<Command>();
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let thread = thread::Builder::new()
+            .name("audio".into())
+            .spawn(move || {
+                let mut service = make_service();
+                let shared = Arc::new(Mutex::new(PlayerView::of(&service, 0)));
+                if ready_tx.send(shared.clone()).is_err() {
+                    return;
+                }
+                Self::run(&mut service, &receiver, &shared);
+            })
+            .expect("spawn audio thread");
+
+        let shared = ready_rx.recv().expect("audio thread failed to start");
+        let view = shared.lock().expect("player view poisoned").clone();
+        Self {
+            commands: Some(commands),
+            shared,
+            view,
+            sent: 0,
+            previews: Vec::new(),
+            loads: mpsc::channel(),
+            load_id: 0,
+            loading: None,
+            thread: Some(thread),
+        }
+    }
+
+    fn run(service: &mut MusicService, commands: &Receiver<Command>, shared: &Mutex<PlayerView>) {
+        let mut applied = 0;
+        loop {
+            match commands.recv_timeout(TICK) {
+                Ok(command) => {
+                    command(service);
+                    applied += 1;
+                    for command in commands.try_iter() {
+                        command(service);
+                        applied += 1;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            service.update();
+            *shared.lock().expect("player view poisoned") = PlayerView::of(service, applied);
+        }
+    }
+
+    fn send(&mut self, command: impl FnOnce(&mut MusicService) + Send + 'static) {
+        if let Some(commands) = &self.commands
+            && commands.send(Box::new(command)).is_ok()
+        {
+            self.sent += 1;
+        }
+    }
+
+    /// Like [`Player::send`], but shows the command's effect on the view right away.
+    fn send_previewed(
+        &mut self,
+        preview: impl Fn(&mut PlayerView) + 'static,
+        command: impl FnOnce(&mut MusicService) + Send + 'static,
+    ) {
+        preview(&mut self.view);
+        let sent = self.sent;
+        self.send(command);
+        if self.sent != sent {
+            self.previews.push((self.sent, Box::new(preview)));
+        }
+    }
+
+    /// Sends a command that opens a track and marks `path` as loading until it's done.
+    fn send_load(
+        &mut self,
+        path: Option<PathBuf>,
+        load: impl FnOnce(&mut MusicService) -> Result<(), MusicOpenError> + Send + 'static,
+    ) {
+        self.load_id += 1;
+        self.loading = path;
+        let id = self.load_id;
+        let done = self.loads.0.clone();
+        self.send(move |service| {
+            let _ = done.send(LoadFinished {
+                id,
+                result: load(service),
+            });
+        });
+    }
+
+    /// Picks up the audio thread's latest state and finished loads. Call once per frame.
+    pub fn poll(&mut self) {
+        let snapshot = self.shared.lock().expect("player view poisoned").clone();
+        // The snapshot may predate commands just sent; keep showing what they change.
+        self.previews
+            .retain(|(command, _)| *command > snapshot.applied);
+        self.view = snapshot;
+        for (_, preview) in &self.previews {
+            preview(&mut self.view);
+        }
+
+        for finished in self.loads.1.try_iter() {
+            if let Err(e) = &finished.result {
+                eprintln!("Failed to load music: {}", e);
+            }
+            if finished.id == self.load_id {
+                self.loading = None;
+            }
+        }
+    }
+
+    pub fn view(&self) -> &PlayerView {
+        &self.view
+    }
+
+    /// The track being opened on the audio thread, if any.
+    pub fn loading(&self) -> Option<&std::path::Path> {
+        self.loading.as_deref()
+    }
+
+    /// Receives the service's playback events from now on.
+    pub fn subscribe(&mut self) -> Receiver<PlaybackEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(move |service| service.add_subscriber(sender));
+        receiver
+    }
+
+    // --- TRANSPORT ---
+
+    pub fn play_tracks(&mut self, tracks: Vec<PathBuf>, index: usize) {
+        let path = tracks.get(index).cloned();
+        self.send_load(path, move |service| service.play_tracks(tracks, index));
+    }
+
+    pub fn play_next(&mut self) {
+        self.send_load(None, |service| service.play_next().map(|_| ()));
+    }
+
+    pub fn play_previous(&mut self) {
+        self.send_load(None, |service| service.play_previous().map(|_| ()));
+    }
+
+    pub fn pause(&mut self) {
+        self.send(MusicService::pause);
+    }
+
+    pub fn resume(&mut self) {
+        self.send(MusicService::resume);
+    }
+
+    pub fn stop(&mut self) {
+        self.send(MusicService::stop);
+    }
+
+    pub fn set_pos(&mut self, pos: Duration) {
+        self.send(move |service| service.set_pos(pos));
+    }
+
+    /// Jumps to where the current track was left off last time.
+    pub fn accept_resume_offer(&mut self) {
+        self.send_previewed(
+            |view| view.resume_offer = None,
+            MusicService::accept_resume_offer,
+        );
+    }
+
+    pub fn dismiss_resume_offer(&mut self) {
+        self.send_previewed(
+            |view| view.resume_offer = None,
+            MusicService::dismiss_resume_offer,
+        );
+    }
+
+    pub fn seek_forward(&mut self, by: Duration) {
+        self.send(move |service| service.seek_forward(by));
+    }
+
+    pub fn seek_backward(&mut self, by: Duration) {
+        self.send(move |service| service.seek_backward(by));
+    }
+
+    // --- QUEUE ---
+
+    pub fn enqueue(&mut self, path: PathBuf) {
+        self.send(move |service| service.enqueue(path));
+    }
+
+    pub fn insert_next(&mut self, path: PathBuf) {
+        self.send(move |service| service.insert_next(path));
+    }
+
+    pub fn set_playback_mode(&mut self, mode: PlaybackMode) {
+        self.send_previewed(
+            move |view| view.playback_mode = mode,
+            move |service| service.set_playback_mode(mode),
+        );
+    }
+
+    pub fn toggle_shuffle(&mut self) {
+        self.send_previewed(
+            |view| view.playback_mode.shuffle = !view.playback_mode.shuffle,
+            MusicService::toggle_shuffle,
+        );
+    }
+
+    pub fn cycle_repeat(&mut self) {
+        self.send_previewed(
+            |view| view.playback_mode.repeat = view.playback_mode.repeat.cycle(),
+            MusicService::cycle_repeat,
+        );
+    }
+
+    // --- SETTINGS ---
+
+    pub fn set_volume_state(&mut self, volume: Volume) {
+        self.send_previewed(
+            move |view| view.volume = volume,
+            move |service| service.set_volume_state(volume),
+        );
+    }
+
+    pub fn set_volume(&mut self, level: f32) {
+        self.send_previewed(
+            move |view| view.volume.set_level(level),
+            move |service| service.set_volume(level),
+        );
+    }
+
+    pub fn toggle_mute(&mut self) {
+        let muted = !self.view.volume.is_muted();
+        self.send_previewed(
+            move |view| view.volume.set_muted(muted),
+            move |service| service.set_muted(muted),
+        );
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.send_previewed(
+            move |view| view.speed = speed,
+            move |service| service.set_speed(speed),
+        );
+    }
+
+    pub fn set_transport_ramp(&mut self, ramp: Duration) {
+        self.send_previewed(
+            move |view| view.transport_ramp = ramp,
+            move |service| service.set_transport_ramp(ramp),
+        );
+    }
+
+    pub fn set_crossfade(&mut self, settings: CrossfadeSettings) {
+        self.send_previewed(
+            move |view| view.crossfade = settings,
+            move |service| service.set_crossfade(settings),
+        );
+    }
+
+    pub fn set_replay_gain(&mut self, settings: ReplayGainSettings) {
+        self.send_previewed(
+            move |view| view.replay_gain = settings,
+            move |service| service.set_replay_gain(settings),
+        );
+    }
+
+    pub fn set_output_device(&mut self, name: Option<String>) {
+        let preview = name.clone();
+        self.send_previewed(
+            move |view| view.output_device = preview.clone(),
+            move |service| service.set_output_device(name),
+        );
+    }
+
+    pub fn clear_error(&mut self) {
+        self.send_previewed(|view| view.last_error = None, MusicService::clear_error);
+    }
+
+    // --- SLEEP TIMER ---
+
+    pub fn set_sleep_timer(&mut self, mode: SleepMode) {
+        let timer = SleepTimer::new(mode, Instant::now());
+        self.send_previewed(
+            move |view| view.sleep_timer = Some(timer),
+            move |service| service.set_sleep_timer(mode),
+        );
+    }
+
+    pub fn extend_sleep_timer(&mut self) {
+        self.send_previewed(
+            |view| {
+                if let Some(timer) = &mut view.sleep_timer {
+                    timer.extend();
+                }
+            },
+            MusicService::extend_sleep_timer,
+        );
+    }
+
+    pub fn cancel_sleep_timer(&mut self) {
+        self.send_previewed(
+            |view| {
+                view.sleep_timer = None;
+                view.sleep_timer_remaining = None;
+            },
+            MusicService::cancel_sleep_timer,
+        );
+    }
+
+    // --- RESUME POINTS ---
+
+    pub fn set_resume_settings(&mut self, settings: ResumeSettings) {
+        self.send_previewed(
+            move |view| {
+                view.resume_settings = settings;
+                Arc::make_mut(&mut view.resume_points).set_settings(settings);
+                if !settings.enabled {
+                    view.resume_offer = None;
+                }
+            },
+            move |service| service.set_resume_settings(settings),
+        );
+    }
+
+    pub fn set_resume_points(&mut self, resume_points: ResumePoints) {
+        let preview = Arc::new(resume_points.clone());
+        self.send_previewed(
+            move |view| {
+                view.resume_settings = preview.settings();
+                view.resume_points = preview.clone();
+            },
+            move |service| service.set_resume_points(resume_points),
+        );
+    }
+
+    /// Shows rewritten tags for `path` if it is playing or up next.
+    pub fn update_tags(&mut self, path: PathBuf, tags: TrackTags) {
+        self.send(move |service| service.update_tags(&path, tags));
+    }
+
+    // --- EQUALIZER ---
+
+    pub fn set_equalizer_params(&mut self, params: EqParams) {
+        let preview = params.clone();
+        self.send_previewed(
+            move |view| Arc::make_mut(&mut view.equalizer).params = preview.clone(),
+            move |service| service.set_equalizer_params(params),
+        );
+    }
+
+    pub fn set_equalizer_state(&mut self, state: EqualizerState) {
+        let preview = Arc::new(state.clone());
+        self.send_previewed(
+            move |view| view.equalizer = preview.clone(),
+            move |service| service.set_equalizer_state(state),
+        );
+    }
+
+    pub fn apply_eq_preset(&mut self, preset: EqPreset) {
+        let preview = preset.clone();
+        self.send_previewed(
+            move |view| {
+                let params = &mut Arc::make_mut(&mut view.equalizer).params;
+                preview.apply_to(params);
+                params.enabled = true;
+            },
+            move |service| service.apply_eq_preset(&preset),
+        );
+    }
+
+    pub fn save_eq_preset(&mut self, name: String) {
+        self.send(move |service| service.save_eq_preset(&name));
+    }
+
+    pub fn delete_eq_preset(&mut self, name: String) {
+        self.send(move |service| service.delete_eq_preset(&name));
+    }
+
+    // --- A-B LOOP ---
+
+    pub fn set_ab_loop(&mut self, ab_loop: AbLoop) {
+        self.send_previewed(
+            move |view| view.ab_loop = ab_loop,
+            move |service| service.set_ab_loop(ab_loop),
+        );
+    }
+
+    pub fn set_loop_a(&mut self) {
+        self.send(MusicService::set_loop_a);
+    }
+
+    pub fn set_loop_b(&mut self) {
+        self.send(MusicService::set_loop_b);
+    }
+
+    pub fn clear_ab_loop(&mut self) {
+        self.send_previewed(
+            |view| view.ab_loop = AbLoop::default(),
+            MusicService::clear_ab_loop,
+        );
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        // Closing the channel ends the audio thread's loop.
+        self.commands = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    /// Replays the current track when it finishes.
+    One,
+    /// Starts over from the top of the play order after the last track.
+    All,
+}
+
+impl RepeatMode {
+    pub fn cycle(self) -> Self {
+        match self {
+            RepeatMode::Off => RepeatMode::All,
+            RepeatMode::All => RepeatMode::One,
+            RepeatMode::One => RepeatMode::Off,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RepeatMode::Off => "Repeat off",
+            RepeatMode::One => "Repeat one",
+            RepeatMode::All => "Repeat all",
+        }
+    }
+}
+
+/// Shuffle and repeat settings, remembered between runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaybackMode {
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+}
+
+/// Ordered list of tracks owned by `MusicService`, with a cursor on the one playing.
+///
+/// Tracks keep their listing order in `tracks`; `order` is the sequence they are
+/// played in. With shuffle off it is the identity, with shuffle on a random
+/// permutation, so every track plays once per cycle and walking the cursor
+/// backwards retraces what was actually heard.
+#[derive(Clone, Debug)]
+pub struct PlayQueue {
+    tracks: Vec<PathBuf>,
+    order: Vec<usize>,
+    /// Position in `order` of the current track.
+    cursor: Option<usize>,
+    /// The current track was removed; `cursor` now points at the one that took its
+    /// place, which is up next rather than playing.
+    removed_current: bool,
+    mode: PlaybackMode,
+    rng: XorShift,
+}
+
+impl Default for PlayQueue {
+    fn default() -> Self {
+        Self {
+            tracks: Vec::new(),
+            order: Vec::new(),
+            cursor: None,
+            removed_current: false,
+            mode: PlaybackMode::default(),
+            rng: XorShift::from_clock(),
+        }
+    }
+}
+
+impl PlayQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the whole queue, leaving nothing selected.
+    pub fn set_tracks(&mut self, tracks: Vec<PathBuf>) {
+        self.tracks = tracks;
+        self.cursor = None;
+        self.removed_current = false;
+        self.order = (0..self.tracks.len()).collect();
+        if self.mode.shuffle {
+            self.rng.shuffle(&mut self.order);
+        }
+    }
+
+    pub fn enqueue(&mut self, path: impl Into<PathBuf>) {
+        self.tracks.push(path.into());
+        let index = self.tracks.len() - 1;
+
+        // In shuffle mode the new track lands somewhere among the ones still to come.
+        let upcoming_start = self.next_position();
+        let at = if self.mode.shuffle {
+            upcoming_start + self.rng.below(self.order.len() - upcoming_start + 1)
+        } else {
+            self.order.len()
+        };
+        self.order.insert(at, index);
+    }
+
+    /// Inserts a track right after the current one (or at the front when nothing is playing).
+    pub fn insert_next(&mut self, path: impl Into<PathBuf>) {
+        let index = self.current_index().map_or(0, |i| i + 1);
+        self.tracks.insert(index, path.into());
+        for i in &mut self.order {
+            if *i >= index {
+                *i += 1;
+            }
+        }
+        self.order.insert(self.next_position(), index);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<PathBuf> {
+        if index >= self.tracks.len() {
+            return None;
+        }
+        let removed = self.tracks.remove(index);
+
+        let position = self.order.iter().position(|&i| i == index)?;
+        self.order.remove(position);
+        for i in &mut self.order {
+            if *i > index {
+                *i -= 1;
+            }
+        }
+        self.cursor = match self.cursor {
+            Some(cursor) if position < cursor => Some(cursor - 1),
+            Some(cursor) if position == cursor => {
+                self.removed_current = true;
+                Some(cursor)
+            }
+            other => other,
+        };
+        Some(removed)
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+        self.order.clear();
+        self.cursor = None;
+        self.removed_current = false;
+    }
+
+    /// Makes `index` the current track because the user picked it. With shuffle on it
+    /// becomes the latest entry in the history; the unplayed rest stays ahead.
+    pub fn jump_to(&mut self, index: usize) -> Option<&Path> {
+        let position = self.order.iter().position(|&i| i == index)?;
+        if self.mode.shuffle {
+            self.order.remove(position);
+            let next = self.next_position();
+            let cursor = if position < next { next - 1 } else { next };
+            self.order.insert(cursor, index);
+            self.cursor = Some(cursor);
+        } else {
+            self.cursor = Some(position);
+        }
+        self.removed_current = false;
+        self.tracks.get(index).map(PathBuf::as_path)
+    }
+
+    /// Moves the cursor onto `index` without reordering, for automatic transitions
+    /// to a track previously returned by [`PlayQueue::upcoming`].
+    pub fn follow(&mut self, index: usize) -> Option<&Path> {
+        let start = self.next_position();
+        let len = self.order.len();
+        let mut position = (0..len)
+            .map(|offset| (start + offset) % len)
+            .find(|&p| self.order[p] == index)?;
+        if self.mode.repeat == RepeatMode::All && position < start {
+            self.start_cycle(Some(index));
+            position = 0;
+        }
+        self.cursor = Some(position);
+        self.removed_current = false;
+        self.tracks.get(index).map(PathBuf::as_path)
+    }
+
+    /// Skips forward in play order, wrapping around when repeating all.
+    pub fn advance(&mut self) -> Option<&Path> {
+        let next = self.next_position();
+        let position = if next < self.order.len() {
+            next
+        } else if self.mode.repeat == RepeatMode::All && !self.order.is_empty() {
+            self.start_cycle(None);
+            0
+        } else {
+            return None;
+        };
+        self.cursor = Some(position);
+        self.removed_current = false;
+        self.current()
+    }
+
+    /// Skips back through what was played, wrapping only without shuffle.
+    pub fn step_back(&mut self) -> Option<&Path> {
+        let position = match self.cursor? {
+            0 if self.mode.repeat == RepeatMode::All && !self.mode.shuffle => {
+                self.order.len().checked_sub(1)?
+            }
+            cursor => cursor.checked_sub(1)?,
+        };
+        self.cursor = Some(position);
+        self.removed_current = false;
+        self.current()
+    }
+
+    /// Track indices that should play after the current one finishes on its own,
+    /// taking repeat mode into account.
+    pub fn upcoming(&self) -> Vec<usize> {
+        if self.mode.repeat == RepeatMode::One
+            && let Some(current) = self.current_index()
+        {
+            return vec![current];
+        }
+        let start = self.next_position();
+        let mut upcoming: Vec<usize> = self.order[start.min(self.order.len())..].to_vec();
+        if self.mode.repeat == RepeatMode::All {
+            upcoming.extend_from_slice(&self.order[..start.min(self.order.len())]);
+        }
+        upcoming
+    }
+
+    pub fn peek_next(&self) -> Option<&Path> {
+        let index = *self.upcoming().first()?;
+        self.tracks.get(index).map(PathBuf::as_path)
+    }
+
+    pub fn current(&self) -> Option<&Path> {
+        self.tracks.get(self.current_index()?).map(PathBuf::as_path)
+    }
+
+    /// Index into [`PlayQueue::tracks`] of the current track.
+    pub fn current_index(&self) -> Option<usize> {
+        if self.removed_current {
+            return None;
+        }
+        self.order.get(self.cursor?).copied()
+    }
+
+    /// Position in `order` of the track that plays next, before any wrap-around.
+    fn next_position(&self) -> usize {
+        match self.cursor {
+            Some(cursor) if self.removed_current => cursor,
+            Some(cursor) => cursor + 1,
+            None => 0,
+        }
+    }
+
+    /// Repeating all with shuffle on plays each cycle in a fresh order. `first` is
+    /// put in front when the next track is already chosen; otherwise the track that
+    /// just played is kept away from the front so it doesn't play twice in a row.
+    fn start_cycle(&mut self, first: Option<usize>) {
+        if !self.mode.shuffle {
+            return;
+        }
+        let last = self.current_index();
+        self.rng.shuffle(&mut self.order);
+        let len = self.order.len();
+        if let Some(first) = first
+            && let Some(position) = self.order.iter().position(|&i| i == first)
+        {
+            self.order.swap(0, position);
+        } else if len > 1 && last.is_some() && self.order.first().copied() == last {
+            let swap_with = 1 + self.rng.below(len - 1);
+            self.order.swap(0, swap_with);
+        }
+    }
+
+    pub fn tracks(&self) -> &[PathBuf] {
+        &self.tracks
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    // --- SHUFFLE / REPEAT ---
+
+    pub fn mode(&self) -> PlaybackMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: PlaybackMode) {
+        self.set_shuffle(mode.shuffle);
+        self.mode.repeat = mode.repeat;
+    }
+
+    pub fn set_repeat(&mut self, repeat: RepeatMode) {
+        self.mode.repeat = repeat;
+    }
+
+    /// Turning shuffle on keeps the current track and shuffles everything else after it;
+    /// turning it off returns to listing order from the current track.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        if self.mode.shuffle == shuffle {
+            return;
+        }
+        self.mode.shuffle = shuffle;
+
+        let current = self.current_index();
+        self.order = (0..self.tracks.len()).collect();
+        self.removed_current = false;
+        if shuffle {
+            if let Some(current) = current {
+                self.order.swap(0, current);
+                self.rng.shuffle(&mut self.order[1..]);
+                self.cursor = Some(0);
+            } else {
+                self.rng.shuffle(&mut self.order);
+                self.cursor = None;
+            }
+        } else {
+            self.cursor = current;
+        }
+    }
+}
+
+/// Small xorshift generator; shuffling a playlist doesn't need more.
+#[derive(Clone, Debug)]
+struct XorShift(u64);
+
+impl XorShift {
+    fn from_clock() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        // The state must never be zero.
+        Self(nanos | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+
+    /// Fisher–Yates.
+    fn shuffle(&mut self, items: &mut [usize]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(len: usize) -> PlayQueue {
+        let mut queue = PlayQueue::new();
+        queue.set_tracks((0..len).map(path).collect());
+        queue
+    }
+
+    fn path(i: usize) -> PathBuf {
+        PathBuf::from(format!("{i}.mp3"))
+    }
+
+    #[test]
+    fn removing_the_current_track_continues_with_the_next_one() {
+        let mut queue = queue(4);
+        queue.jump_to(1);
+        assert_eq!(queue.remove(1), Some(path(1)));
+        assert_eq!(queue.current(), None);
+        assert_eq!(queue.peek_next(), Some(path(2).as_path()));
+        assert_eq!(queue.advance(), Some(path(2).as_path()));
+        assert_eq!(queue.advance(), Some(path(3).as_path()));
+
+        // Removing the last track leaves nothing to advance to.
+        assert_eq!(queue.remove(2), Some(path(3)));
+        assert_eq!(queue.advance(), None);
+    }
+
+    fn shuffled(len: usize) -> PlayQueue {
+        let mut queue = queue(len);
+        queue.set_shuffle(true);
+        queue
+    }
+
+    fn play(queue: &mut PlayQueue, count: usize) -> Vec<PathBuf> {
+        (0..count)
+            .map_while(|_| queue.advance().map(Path::to_path_buf))
+            .collect()
+    }
+
+    fn sorted(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn shuffle_plays_every_track_once_per_cycle() {
+        let mut queue = shuffled(20);
+        let played = play(&mut queue, 21);
+        assert_eq!(played.len(), 20);
+        assert_eq!(sorted(played), sorted(queue.tracks().to_vec()));
+    }
+
+    #[test]
+    fn stepping_back_retraces_the_shuffled_history() {
+        let mut queue = shuffled(10);
+        let played = play(&mut queue, 6);
+        for expected in played.iter().rev().skip(1) {
+            assert_eq!(queue.step_back(), Some(expected.as_path()));
+        }
+        assert_eq!(queue.step_back(), None);
+
+        // Going forward again replays the same order.
+        assert_eq!(play(&mut queue, 5), played[1..]);
+    }
+
+    #[test]
+    fn repeat_all_reshuffles_each_cycle() {
+        let mut queue = shuffled(5);
+        queue.set_repeat(RepeatMode::All);
+        let mut last = None;
+        let mut orders = Vec::new();
+        for _ in 0..50 {
+            let cycle = play(&mut queue, 5);
+            assert_eq!(sorted(cycle.clone()), sorted(queue.tracks().to_vec()));
+            assert_ne!(cycle.first(), last.as_ref());
+            last = cycle.last().cloned();
+            if !orders.contains(&cycle) {
+                orders.push(cycle);
+            }
+        }
+        assert!(orders.len() > 1);
+    }
+
+    #[test]
+    fn repeat_all_reshuffles_when_following_the_wrap() {
+        let mut queue = shuffled(5);
+        queue.set_repeat(RepeatMode::All);
+        play(&mut queue, 5);
+        let first = queue.upcoming()[0];
+        let path = queue.tracks()[first].clone();
+        assert_eq!(queue.follow(first), Some(path.as_path()));
+        assert_eq!(queue.current_index(), Some(first));
+
+        // The new cycle starts at the followed track and still covers everything.
+        let mut cycle = vec![path];
+        cycle.extend(play(&mut queue, 4));
+        assert_eq!(sorted(cycle), sorted(queue.tracks().to_vec()));
+    }
+
+    #[test]
+    fn repeat_one_stays_on_the_current_track() {
+        let mut queue = shuffled(5);
+        queue.set_repeat(RepeatMode::One);
+        let current = queue.advance().map(Path::to_path_buf);
+        let index = queue.current_index().unwrap();
+        assert_eq!(queue.upcoming(), vec![index]);
+        queue.follow(index);
+        assert_eq!(queue.current().map(Path::to_path_buf), current);
+
+        // Skipping still moves on, and the end doesn't wrap.
+        assert_eq!(play(&mut queue, 10).len(), 4);
+    }
+
+    #[test]
+    fn enqueued_tracks_join_the_unplayed_part_of_a_shuffle() {
+        let mut queue = shuffled(8);
+        let played = play(&mut queue, 3);
+        queue.enqueue("new.mp3");
+
+        let rest = play(&mut queue, 10);
+        assert_eq!(rest.len(), 6);
+        assert!(rest.contains(&PathBuf::from("new.mp3")));
+        let mut all = played.clone();
+        all.extend(rest);
+        assert_eq!(sorted(all), sorted(queue.tracks().to_vec()));
+
+        // The history before it is untouched.
+        for _ in 0..6 {
+            queue.step_back();
+        }
+        assert_eq!(queue.current(), Some(played[2].as_path()));
+    }
+
+    #[test]
+    fn removing_while_shuffled_keeps_the_order_of_the_rest() {
+        let mut queue = shuffled(8);
+        let played = play(&mut queue, 3);
+        let upcoming: Vec<PathBuf> = queue
+            .upcoming()
+            .iter()
+            .map(|&i| queue.tracks()[i].clone())
+            .collect();
+
+        // An earlier track doesn't move the cursor.
+        let earlier = queue.tracks().iter().position(|p| *p == played[0]).unwrap();
+        queue.remove(earlier);
+        assert_eq!(queue.current(), Some(played[2].as_path()));
+
+        // The current one hands over to what was up next.
+        queue.remove(queue.current_index().unwrap());
+        assert_eq!(queue.current(), None);
+        assert_eq!(play(&mut queue, 10), upcoming);
+    }
+}
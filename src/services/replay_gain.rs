@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::replay_gain::ReplayGain;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayGainMode {
+    #[default]
+    Off,
+    Track,
+    /// Keeps the relative loudness of tracks within an album.
+    Album,
+}
+
+impl ReplayGainMode {
+    pub const ALL: [ReplayGainMode; 3] = [
+        ReplayGainMode::Off,
+        ReplayGainMode::Track,
+        ReplayGainMode::Album,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReplayGainMode::Off => "Off",
+            ReplayGainMode::Track => "Track",
+            ReplayGainMode::Album => "Album",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplayGainSettings {
+    pub mode: ReplayGainMode,
+    /// Extra gain in dB applied on top of the tagged value.
+    pub preamp_db: f32,
+}
+
+impl ReplayGainSettings {
+    pub const PREAMP_RANGE_DB: std::ops::RangeInclusive<f32> = -15.0..=15.0;
+
+    /// Linear factor for a track, limited by its peak so the result never clips.
+    /// Tracks without ReplayGain tags play unchanged.
+    pub fn factor(&self, replay_gain: &ReplayGain) -> f32 {
+        let (gain, peak) = match self.mode {
+            ReplayGainMode::Off => return 1.0,
+            ReplayGainMode::Track => (
+                replay_gain.track_gain.or(replay_gain.album_gain),
+                replay_gain.track_peak.or(replay_gain.album_peak),
+            ),
+            ReplayGainMode::Album => (
+                replay_gain.album_gain.or(replay_gain.track_gain),
+                replay_gain.album_peak.or(replay_gain.track_peak),
+            ),
+        };
+        let Some(gain) = gain else { return 1.0 };
+
+        let factor = 10f32.powf((gain + self.preamp_db) / 20.0);
+        match peak {
+            Some(peak) if peak > 0.0 => factor.min(1.0 / peak),
+            _ => factor,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResumeSettings {
+    pub enabled: bool,
+    /// Shorter tracks always start from the beginning.
+    pub min_length: Duration,
+}
+
+impl Default for ResumeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_length: Duration::from_secs(20 * 60),
+        }
+    }
+}
+
+impl ResumeSettings {
+    pub const MAX_MIN_LENGTH: Duration = Duration::from_secs(2 * 60 * 60);
+
+    /// Tracks of unknown length are remembered, since they may well be long.
+    pub fn applies_to(&self, length: Option<Duration>) -> bool {
+        self.enabled && length.is_none_or(|length| length >= self.min_length)
+    }
+}
+
+/// Where each long track was left off, keyed by its canonical path.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResumePoints {
+    settings: ResumeSettings,
+    positions: HashMap<String, Duration>,
+}
+
+impl ResumePoints {
+    /// Positions this close to either end aren't worth offering.
+    const MARGIN: Duration = Duration::from_secs(5);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn settings(&self) -> ResumeSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: ResumeSettings) {
+        self.settings = settings;
+    }
+
+    /// The saved position for `path`, if the settings still allow resuming it.
+    pub fn position(&self, path: &Path, length: Option<Duration>) -> Option<Duration> {
+        if !self.settings.applies_to(length) {
+            return None;
+        }
+        self.positions.get(&Self::key(path)).copied()
+    }
+
+    /// Saves `pos` for `path`, or forgets the track if it was barely started or
+    /// nearly finished.
+    pub fn remember(&mut self, path: &Path, pos: Duration, length: Option<Duration>) {
+        if !self.settings.applies_to(length) {
+            return;
+        }
+        let near_end = length.is_some_and(|length| pos + Self::MARGIN >= length);
+        if pos < Self::MARGIN || near_end {
+            self.forget(path);
+            return;
+        }
+        self.positions.insert(Self::key(path), pos);
+    }
+
+    pub fn forget(&mut self, path: &Path) {
+        self.positions.remove(&Self::key(path));
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// The same file reached through different relative paths shares one entry.
+    fn key(path: &Path) -> String {
+        path.canonicalize()
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::audio::fade::FadeCurve;
+
+/// Where timed countdowns read the time from. A manual clock only moves when
+/// advanced, so tests don't have to sleep.
+#[derive(Clone, Debug, Default)]
+pub enum Clock {
+    #[default]
+    System,
+    Manual(Arc<Mutex<Instant>>),
+}
+
+impl Clock {
+    pub fn manual() -> Self {
+        Clock::Manual(Arc::new(Mutex::new(Instant::now())))
+    }
+
+    pub fn now(&self) -> Instant {
+        match self {
+            Clock::System => Instant::now(),
+            Clock::Manual(now) => *now.lock().expect("clock poisoned"),
+        }
+    }
+
+    /// Moves a manual clock forward; the system clock moves on its own.
+    pub fn advance(&self, by: Duration) {
+        if let Clock::Manual(now) = self {
+            *now.lock().expect("clock poisoned") += by;
+        }
+    }
+}
+
+/// What the sleep timer counts down to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SleepMode {
+    After(Duration),
+    EndOfTrack,
+    AfterTracks(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SleepDeadline {
+    At(Instant),
+    /// Tracks still to finish, counting the current one.
+    Tracks(u32),
+}
+
+/// Pauses playback at a deadline, fading the volume out on the way there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SleepTimer {
+    deadline: SleepDeadline,
+    /// Length of the fade in progress, fixed when it starts so the level only falls.
+    fade: Option<Duration>,
+}
+
+impl SleepTimer {
+    pub const FADE: Duration = Duration::from_secs(45);
+    /// How much "extend" adds to a timed countdown.
+    pub const EXTENSION: Duration = Duration::from_secs(5 * 60);
+
+    pub fn new(mode: SleepMode, now: Instant) -> Self {
+        let deadline = match mode {
+            SleepMode::After(duration) => SleepDeadline::At(now + duration),
+            SleepMode::EndOfTrack => SleepDeadline::Tracks(1),
+            SleepMode::AfterTracks(tracks) => SleepDeadline::Tracks(tracks.max(1)),
+        };
+        Self {
+            deadline,
+            fade: None,
+        }
+    }
+
+    pub fn deadline(&self) -> SleepDeadline {
+        self.deadline
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        match self.deadline {
+            SleepDeadline::At(at) => now >= at,
+            SleepDeadline::Tracks(tracks) => tracks == 0,
+        }
+    }
+
+    /// Time until the deadline, given the playback time left in the current track.
+    /// Unknown while more than one track is still to go.
+    pub fn remaining(&self, track_remaining: Option<Duration>, now: Instant) -> Option<Duration> {
+        match self.deadline {
+            SleepDeadline::At(at) => Some(at.saturating_duration_since(now)),
+            SleepDeadline::Tracks(0) => Some(Duration::ZERO),
+            SleepDeadline::Tracks(1) => track_remaining,
+            SleepDeadline::Tracks(_) => None,
+        }
+    }
+
+    /// The timer stops with the current track, so nothing may be queued behind it.
+    pub fn ends_with_current_track(&self) -> bool {
+        self.deadline == SleepDeadline::Tracks(1)
+    }
+
+    pub fn track_finished(&mut self) {
+        if let SleepDeadline::Tracks(tracks) = &mut self.deadline {
+            *tracks = tracks.saturating_sub(1);
+            self.fade = None;
+        }
+    }
+
+    /// Pushes the deadline back by [`Self::EXTENSION`] or one more track.
+    pub fn extend(&mut self) {
+        match &mut self.deadline {
+            SleepDeadline::At(at) => *at += Self::EXTENSION,
+            SleepDeadline::Tracks(tracks) => *tracks += 1,
+        }
+        self.fade = None;
+    }
+
+    /// Volume factor for this moment: 1.0 until the last [`Self::FADE`], then falling
+    /// to silence at the deadline.
+    pub fn level(&mut self, track_remaining: Option<Duration>, now: Instant) -> f32 {
+        let Some(remaining) = self.remaining(track_remaining, now) else {
+            return 1.0;
+        };
+        if remaining > Self::FADE {
+            // Seeking back out of the fade window starts it over later.
+            self.fade = None;
+            return 1.0;
+        }
+        let fade = *self.fade.get_or_insert(remaining);
+        if fade.is_zero() {
+            return 0.0;
+        }
+        FadeCurve::Logarithmic.gain(remaining.as_secs_f32() / fade.as_secs_f32())
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+/// Master volume as shown on the slider, plus the mute toggle.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Volume {
+    level: f32,
+    muted: bool,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self {
+            level: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl Volume {
+    /// Slider step used for the mouse wheel and keyboard.
+    pub const STEP: f32 = 0.05;
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    pub fn set_level(&mut self, level: f32) {
+        self.level = level.clamp(0.0, 1.0);
+        // Dragging the volume back up is the usual way people expect to unmute.
+        if self.level > 0.0 {
+            self.muted = false;
+        }
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+    }
+
+    /// Amplitude factor for the sink. Loudness is perceived roughly logarithmically,
+    /// so a cubic curve makes the slider feel even across its whole range.
+    pub fn gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.level.powi(3) }
+    }
+}
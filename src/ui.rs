@@ -1,3 +1,9 @@
+pub mod artwork_ui;
+pub mod batch_editor_ui;
+pub mod equalizer_ui;
 pub mod music_buttons;
 pub mod music_path_entry_ui;
 pub mod music_list;
+pub mod settings_ui;
+pub mod sleep_timer_ui;
+pub mod tag_editor_ui;
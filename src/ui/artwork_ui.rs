@@ -0,0 +1,220 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use eframe::egui::{self, ColorImage, TextureHandle};
+
+use crate::models::{
+    tags::picture::{Picture, PictureKind},
+    track::Track,
+};
+use crate::services::player::Player;
+use crate::utils::texture_util;
+
+/// Scans can be huge; anything larger is scaled down before it becomes a texture.
+const MAX_IMAGE_SIZE: u32 = 1024;
+const THUMBNAIL_SIZE: f32 = 96.0;
+
+struct DecodedImage {
+    label: String,
+    /// Size of the original image, before scaling down.
+    size: (u32, u32),
+    image: ColorImage,
+}
+
+struct ArtworkImage {
+    label: String,
+    size: (u32, u32),
+    texture: TextureHandle,
+}
+
+/// Cover thumbnail for the playing track, and a viewer for all of its artwork.
+pub struct ArtworkUI {
+    /// Track the images belong to.
+    loaded_for: Option<PathBuf>,
+    loading: Option<Receiver<Vec<DecodedImage>>>,
+    images: Vec<ArtworkImage>,
+    selected: usize,
+    viewer_open: bool,
+}
+
+impl Default for ArtworkUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArtworkUI {
+    pub fn new() -> Self {
+        Self {
+            loaded_for: None,
+            loading: None,
+            images: Vec::new(),
+            selected: 0,
+            viewer_open: false,
+        }
+    }
+
+    /// Loads the artwork of a newly playing track in the background, and picks
+    /// up the decoded images once they are ready.
+    pub fn update(&mut self, ctx: &egui::Context, player: &Player) {
+        let current = player.view().current_path.clone();
+        if current != self.loaded_for {
+            self.loaded_for = current.clone();
+            self.images.clear();
+            self.selected = 0;
+            self.loading = current.map(load_artwork);
+        }
+
+        if let Some(loading) = &self.loading
+            && let Ok(decoded) = loading.try_recv()
+        {
+            self.loading = None;
+            self.images = decoded
+                .into_iter()
+                .enumerate()
+                .map(|(i, decoded)| ArtworkImage {
+                    texture: ctx.load_texture(
+                        format!("artwork-{}", i),
+                        decoded.image,
+                        Default::default(),
+                    ),
+                    label: decoded.label,
+                    size: decoded.size,
+                })
+                .collect();
+        }
+    }
+
+    /// The cover, or nothing while there is none; clicking opens the viewer.
+    pub fn show_thumbnail(&mut self, ui: &mut egui::Ui) {
+        let Some(cover) = self.images.first() else {
+            return;
+        };
+        let response = ui
+            .add(
+                egui::Image::new(&cover.texture)
+                    .max_size(egui::vec2(THUMBNAIL_SIZE, THUMBNAIL_SIZE))
+                    .corner_radius(4.0)
+                    .sense(egui::Sense::click()),
+            )
+            .on_hover_text(match self.images.len() {
+                1 => "Show artwork".to_owned(),
+                n => format!("Show artwork ({} images)", n),
+            });
+        if response.clicked() {
+            self.selected = 0;
+            self.viewer_open = true;
+        }
+    }
+
+    /// Every image of the track, one at a time, with ◀ ▶ to flip through them.
+    pub fn show_viewer(&mut self, ctx: &egui::Context) {
+        if !self.viewer_open || self.images.is_empty() {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("🖼 Artwork")
+            .id(egui::Id::new("artwork_viewer"))
+            .open(&mut open)
+            .default_size([520.0, 600.0])
+            .show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for (i, image) in self.images.iter().enumerate() {
+                        if ui
+                            .selectable_label(i == self.selected, &image.label)
+                            .clicked()
+                        {
+                            self.selected = i;
+                        }
+                    }
+                });
+                ui.separator();
+
+                let count = self.images.len();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.selected > 0, egui::Button::new("◀"))
+                        .clicked()
+                    {
+                        self.selected -= 1;
+                    }
+                    if ui
+                        .add_enabled(self.selected + 1 < count, egui::Button::new("▶"))
+                        .clicked()
+                    {
+                        self.selected += 1;
+                    }
+                    let image = &self.images[self.selected];
+                    ui.weak(format!(
+                        "{} of {} · {}×{}",
+                        self.selected + 1,
+                        count,
+                        image.size.0,
+                        image.size.1
+                    ));
+                });
+
+                let image = &self.images[self.selected];
+                ui.centered_and_justified(|ui| {
+                    ui.add(egui::Image::new(&image.texture).max_size(ui.available_size()));
+                });
+            });
+        self.viewer_open = open;
+    }
+}
+
+/// Reads and decodes the artwork of `path` on a background thread.
+fn load_artwork(path: PathBuf) -> Receiver<Vec<DecodedImage>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let artwork = match Track::new(&path) {
+            Ok(track) => track.artwork(),
+            Err(e) => {
+                eprintln!("Failed to read artwork of {}: {e}", path.display());
+                Vec::new()
+            }
+        };
+        let decoded = artwork.iter().filter_map(decode).collect();
+        let _ = sender.send(decoded);
+    });
+    receiver
+}
+
+fn decode(picture: &Picture) -> Option<DecodedImage> {
+    let image = match image::load_from_memory(&picture.data) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!(
+                "Skipping undecodable picture {:?}: {e}",
+                picture.description
+            );
+            return None;
+        }
+    };
+    let size = (image.width(), image.height());
+    let image = if size.0.max(size.1) > MAX_IMAGE_SIZE {
+        image.thumbnail(MAX_IMAGE_SIZE, MAX_IMAGE_SIZE)
+    } else {
+        image
+    };
+    let kind = match picture.kind {
+        PictureKind::FrontCover => "Front cover",
+        PictureKind::BackCover => "Back cover",
+        PictureKind::Leaflet => "Booklet",
+        PictureKind::Media => "Disc",
+        PictureKind::Artist => "Artist",
+        PictureKind::Other => "Artwork",
+    };
+    let label = match picture.description.trim() {
+        "" => kind.to_owned(),
+        description => format!("{} — {}", kind, description),
+    };
+    Some(DecodedImage {
+        label,
+        size,
+        image: texture_util::color_image(image),
+    })
+}
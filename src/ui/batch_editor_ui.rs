@@ -0,0 +1,275 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use eframe::egui;
+
+use crate::models::{
+    metadata::TrackMetadata,
+    tags::{
+        TrackTags,
+        batch::{BatchEdit, PlannedEdit},
+        writer::{self, TagChanges},
+    },
+};
+use crate::services::player::Player;
+
+/// A shareable field; unticked ones are left as they are on every track.
+struct SharedField {
+    label: &'static str,
+    enabled: bool,
+    value: String,
+    /// The tracks disagree, so there is nothing to prefill.
+    mixed: bool,
+}
+
+struct BatchSession {
+    /// In list order, which is also the numbering order.
+    tracks: Vec<(PathBuf, TrackMetadata)>,
+    /// Album, album artist, genre, year.
+    fields: [SharedField; 4],
+    number_tracks: bool,
+    writing: Option<Receiver<(PathBuf, io::Result<TrackTags>)>>,
+    /// Files sent for writing and how many have come back.
+    pending: usize,
+    written: usize,
+    errors: Vec<String>,
+}
+
+impl BatchSession {
+    fn edit(&self) -> BatchEdit {
+        let value = |i: usize| {
+            let field: &SharedField = &self.fields[i];
+            field.enabled.then(|| field.value.clone())
+        };
+        BatchEdit {
+            shared: TagChanges {
+                album: value(0),
+                album_artist: value(1),
+                genre: value(2),
+                year: value(3),
+                ..TagChanges::default()
+            },
+            number_tracks: self.number_tracks,
+        }
+    }
+}
+
+/// Dialog for setting album-wide tags on several tracks, with a preview of every
+/// change before anything is written.
+pub struct BatchEditorUI {
+    session: Option<BatchSession>,
+}
+
+impl Default for BatchEditorUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchEditorUI {
+    pub fn new() -> Self {
+        Self { session: None }
+    }
+
+    pub fn open(&mut self, tracks: Vec<(PathBuf, TrackMetadata)>) {
+        let metadata: Vec<TrackMetadata> = tracks.iter().map(|(_, m)| m.clone()).collect();
+        let common = BatchEdit::common_values(&metadata);
+        let any = |field: fn(&TrackMetadata) -> bool| metadata.iter().any(field);
+        let field = |label, common: Option<String>, tagged: bool| SharedField {
+            label,
+            enabled: false,
+            mixed: common.is_none() && tagged,
+            value: common.unwrap_or_default(),
+        };
+
+        self.session = Some(BatchSession {
+            fields: [
+                field("Album", common.album, any(|m| m.album.is_some())),
+                field(
+                    "Album artist",
+                    common.album_artist,
+                    any(|m| m.album_artist.is_some()),
+                ),
+                field("Genre", common.genre, any(|m| m.genre.is_some())),
+                field("Year", common.year, any(|m| m.year.is_some())),
+            ],
+            tracks,
+            number_tracks: false,
+            writing: None,
+            pending: 0,
+            written: 0,
+            errors: Vec::new(),
+        });
+    }
+
+    /// Shows the dialog while tracks are being edited. Returns every file written
+    /// since the last frame with its fresh tags, for the library to pick up.
+    pub fn show(&mut self, ctx: &egui::Context, player: &mut Player) -> Vec<(PathBuf, TrackTags)> {
+        let mut saved = Vec::new();
+        let Some(session) = self.session.as_mut() else {
+            return saved;
+        };
+
+        if let Some(writing) = &session.writing {
+            for (path, result) in writing.try_iter() {
+                session.written += 1;
+                match result {
+                    Ok(tags) => {
+                        if let Some((_, metadata)) =
+                            session.tracks.iter_mut().find(|(p, _)| *p == path)
+                        {
+                            *metadata = tags.metadata.clone();
+                        }
+                        player.update_tags(path.clone(), tags.clone());
+                        saved.push((path, tags));
+                    }
+                    Err(e) => session.errors.push(format!("{}: {}", file_name(&path), e)),
+                }
+            }
+            if session.written == session.pending {
+                session.writing = None;
+                if session.errors.is_empty() {
+                    self.session = None;
+                    return saved;
+                }
+            }
+        }
+
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+        let edit = session.edit();
+        let plan = edit.plan(&session.tracks);
+        let valid = edit.shared.validate();
+
+        egui::Window::new(format!("✏ Edit {} tracks", session.tracks.len()))
+            .id(egui::Id::new("batch_editor"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let writing = session.writing.is_some();
+                ui.add_enabled_ui(!writing, |ui| {
+                    egui::Grid::new("batch_editor_fields")
+                        .num_columns(2)
+                        .spacing([10.0, 6.0])
+                        .show(ui, |ui| {
+                            for field in &mut session.fields {
+                                ui.checkbox(&mut field.enabled, field.label);
+                                let hint = if field.mixed { "(mixed)" } else { "" };
+                                if ui
+                                    .add(
+                                        egui::TextEdit::singleline(&mut field.value)
+                                            .hint_text(hint),
+                                    )
+                                    .changed()
+                                {
+                                    field.enabled = true;
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    ui.checkbox(
+                        &mut session.number_tracks,
+                        format!("Number tracks 1–{} in list order", session.tracks.len()),
+                    );
+                });
+
+                ui.add_space(5.0);
+                ui.label(egui::RichText::new("Preview").strong());
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| show_plan(ui, &plan));
+
+                if let Err(e) = &valid {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {}", e));
+                }
+                for error in &session.errors {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {}", error));
+                }
+
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    let ready = !writing && !plan.is_empty() && valid.is_ok();
+                    apply = ui
+                        .add_enabled(
+                            ready,
+                            egui::Button::new(format!("💾 Apply to {} files", plan.len())),
+                        )
+                        .clicked();
+                    cancel = ui
+                        .add_enabled(!writing, egui::Button::new("Cancel"))
+                        .clicked();
+                    if writing {
+                        let progress = session.written as f32 / session.pending.max(1) as f32;
+                        ui.add(
+                            egui::ProgressBar::new(progress)
+                                .text(format!("{}/{}", session.written, session.pending)),
+                        );
+                    }
+                });
+            });
+
+        if apply {
+            // Files are rewritten one at a time off the UI thread; one failure
+            // doesn't stop the rest.
+            let (sender, receiver) = mpsc::channel();
+            session.pending = plan.len();
+            session.written = 0;
+            session.errors.clear();
+            thread::spawn(move || {
+                for edit in plan {
+                    let result = writer::write_to_path(&edit.path, &edit.changes);
+                    if sender.send((edit.path, result)).is_err() {
+                        return;
+                    }
+                }
+            });
+            session.writing = Some(receiver);
+        }
+        if (!open || cancel) && session.writing.is_none() {
+            self.session = None;
+        }
+        saved
+    }
+}
+
+/// "file: Field old → new" for every file that would change.
+fn show_plan(ui: &mut egui::Ui, plan: &[PlannedEdit]) {
+    if plan.is_empty() {
+        ui.weak("Nothing to change");
+        return;
+    }
+    for edit in plan {
+        ui.label(file_name(&edit.path));
+        for change in &edit.diff {
+            let old = if change.old.is_empty() {
+                "—"
+            } else {
+                &change.old
+            };
+            let new = if change.new.is_empty() {
+                "—"
+            } else {
+                &change.new
+            };
+            ui.horizontal(|ui| {
+                ui.add_space(12.0);
+                ui.weak(format!("{}:", change.field));
+                ui.label(egui::RichText::new(old).strikethrough());
+                ui.label("→");
+                ui.label(egui::RichText::new(new).strong());
+            });
+        }
+    }
+}
+
+fn file_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
@@ -0,0 +1,121 @@
+use eframe::egui;
+
+use crate::audio::equalizer::GAIN_RANGE_DB;
+use crate::services::player::Player;
+use crate::ui::music_buttons::MusicButtons;
+
+pub struct EqualizerUI {
+    new_preset_name: String,
+}
+
+impl Default for EqualizerUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EqualizerUI {
+    pub fn new() -> Self {
+        Self {
+            new_preset_name: String::new(),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, player: &mut Player, buttons: &MusicButtons) {
+        egui::CollapsingHeader::new("🎚 Equalizer")
+            .id_salt("equalizer")
+            .show(ui, |ui| {
+                self.show_presets(ui, player);
+                ui.add_space(5.0);
+                Self::show_bands(ui, player, buttons);
+            });
+    }
+
+    fn show_presets(&mut self, ui: &mut egui::Ui, player: &mut Player) {
+        let mut params = player.view().equalizer.params.clone();
+
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut params.enabled, "Enabled").changed() {
+                player.set_equalizer_params(params.clone());
+            }
+
+            let mut chosen = None;
+            egui::ComboBox::from_id_salt("eq_preset")
+                .selected_text("Presets")
+                .show_ui(ui, |ui| {
+                    for preset in player.view().eq_presets() {
+                        if ui.selectable_label(false, &preset.name).clicked() {
+                            chosen = Some(preset);
+                        }
+                    }
+                });
+            if let Some(preset) = chosen {
+                self.new_preset_name = preset.name.clone();
+                player.apply_eq_preset(preset);
+            }
+
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_preset_name)
+                    .hint_text("Preset name")
+                    .desired_width(100.0),
+            );
+            let name = self.new_preset_name.trim().to_string();
+            let is_user_preset = player
+                .view()
+                .equalizer
+                .user_presets
+                .iter()
+                .any(|p| p.name == name);
+
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("💾 Save"))
+                .clicked()
+            {
+                player.save_eq_preset(name.clone());
+            }
+            if ui
+                .add_enabled(is_user_preset, egui::Button::new("🗑 Delete"))
+                .clicked()
+            {
+                player.delete_eq_preset(name);
+            }
+        });
+    }
+
+    fn show_bands(ui: &mut egui::Ui, player: &mut Player, buttons: &MusicButtons) {
+        let mut params = player.view().equalizer.params.clone();
+        let mut changed = false;
+
+        ui.add_enabled_ui(params.enabled, |ui| {
+            ui.horizontal(|ui| {
+                for (index, band) in params.bands.iter_mut().enumerate() {
+                    ui.push_id(index, |ui| {
+                        ui.vertical(|ui| {
+                            changed |= buttons
+                                .band_slider(ui, &mut band.gain_db, GAIN_RANGE_DB)
+                                .changed();
+                            ui.label(
+                                egui::RichText::new(Self::format_frequency(band.frequency))
+                                    .monospace()
+                                    .size(10.0)
+                                    .color(ui.visuals().weak_text_color()),
+                            );
+                        });
+                    });
+                }
+            });
+        });
+
+        if changed {
+            player.set_equalizer_params(params);
+        }
+    }
+
+    fn format_frequency(frequency: f32) -> String {
+        if frequency >= 1000.0 {
+            format!("{}k", frequency / 1000.0)
+        } else {
+            format!("{}", frequency)
+        }
+    }
+}
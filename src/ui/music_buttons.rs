@@ -2,15 +2,26 @@ use eframe::egui::{
     self, Color32, Painter, Pos2, Rect, Response, Sense, Shape, Stroke, Vec2, vec2,
 };
 
+use std::time::Duration;
+
+use crate::services::{ab_loop::AbLoop, queue::RepeatMode, volume::Volume};
 use crate::utils::color_util;
 
 pub struct MusicButtons {
     desired_size: Vec2,
 }
 
+impl Default for MusicButtons {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MusicButtons {
     const BUTTON_BG_IDLE: Color32 = Color32::from_rgb(30, 30, 35);
     const BUTTON_BG_HOVER: Color32 = Color32::from_rgb(45, 45, 50);
+    /// Tracks shorter than this show the time to a tenth of a second.
+    pub const SHORT_CLIP: Duration = Duration::from_secs(60);
 
     pub fn new() -> Self {
         Self {
@@ -80,19 +91,281 @@ impl MusicButtons {
         response
     }
 
+    // --- PREVIOUS BUTTON ---
+    pub fn show_previous_button(&self, ui: &mut egui::Ui) -> egui::Response {
+        self.show_skip_button(ui, false)
+    }
+
+    // --- NEXT BUTTON ---
+    pub fn show_next_button(&self, ui: &mut egui::Ui) -> egui::Response {
+        self.show_skip_button(ui, true)
+    }
+
+    fn show_skip_button(&self, ui: &mut egui::Ui, forward: bool) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(self.desired_size, Sense::click());
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter();
+            let how_hovered = ui.ctx().animate_bool(response.id, response.hovered());
+
+            self.handle_button_background(ui, &response, painter, &rect, how_hovered);
+
+            let (triangle, bar) = Self::calculate_skip_icon_shapes(painter, &rect, forward);
+            let icon_color = self.get_themed_icon_color(ui, &response, how_hovered);
+
+            painter.add(Shape::convex_polygon(triangle, icon_color, Stroke::NONE));
+            painter.rect_filled(bar, 1.0, icon_color);
+        }
+        self.apply_cursor(&response, ui);
+        response
+    }
+
+    // --- MUTE BUTTON ---
+    // --- SHUFFLE / REPEAT BUTTONS ---
+    pub fn show_shuffle_button(&self, ui: &mut egui::Ui, active: bool) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(vec2(30.0, 25.0), Sense::click());
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter();
+            let how_hovered = ui.ctx().animate_bool(response.id, response.hovered());
+
+            self.handle_button_background(ui, &response, painter, &rect, how_hovered);
+
+            let icon_color = self.get_toggle_icon_color(ui, &response, how_hovered, active);
+            let stroke = Stroke::new(1.5, icon_color);
+            for (path, head) in Self::calculate_shuffle_icon_shapes(painter, &rect) {
+                painter.add(Shape::line(path, stroke));
+                painter.add(Shape::convex_polygon(head, icon_color, Stroke::NONE));
+            }
+        }
+        self.apply_cursor(&response, ui);
+        response.on_hover_text(if active { "Shuffle on" } else { "Shuffle off" })
+    }
+
+    pub fn show_repeat_button(&self, ui: &mut egui::Ui, mode: RepeatMode) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(vec2(30.0, 25.0), Sense::click());
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter();
+            let how_hovered = ui.ctx().animate_bool(response.id, response.hovered());
+
+            self.handle_button_background(ui, &response, painter, &rect, how_hovered);
+
+            let active = mode != RepeatMode::Off;
+            let icon_color = self.get_toggle_icon_color(ui, &response, how_hovered, active);
+            let (loop_path, head) = Self::calculate_repeat_icon_shapes(painter, &rect);
+            painter.add(Shape::line(loop_path, Stroke::new(1.5, icon_color)));
+            painter.add(Shape::convex_polygon(head, icon_color, Stroke::NONE));
+
+            if mode == RepeatMode::One {
+                painter.text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "1",
+                    egui::FontId::proportional(8.0),
+                    icon_color,
+                );
+            }
+        }
+        self.apply_cursor(&response, ui);
+        response.on_hover_text(mode.label())
+    }
+
+    pub fn show_mute_button(&self, ui: &mut egui::Ui, muted: bool, level: f32) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(vec2(30.0, 25.0), Sense::click());
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter();
+            let how_hovered = ui.ctx().animate_bool(response.id, response.hovered());
+
+            self.handle_button_background(ui, &response, painter, &rect, how_hovered);
+
+            let icon_color = self.get_themed_icon_color(ui, &response, how_hovered);
+            let (speaker_body, speaker_cone) = Self::calculate_speaker_icon_shapes(painter, &rect);
+            painter.rect_filled(speaker_body, 0.0, icon_color);
+            painter.add(Shape::convex_polygon(speaker_cone, icon_color, Stroke::NONE));
+
+            let stroke = Stroke::new(1.5, icon_color);
+            let origin = Pos2::new(
+                painter.round_to_pixel_center(rect.center().x) + 1.0,
+                painter.round_to_pixel_center(rect.center().y),
+            );
+            if muted || level == 0.0 {
+                let d = 3.0;
+                let c = origin + vec2(4.0, 0.0);
+                painter.line_segment([c + vec2(-d, -d), c + vec2(d, d)], stroke);
+                painter.line_segment([c + vec2(-d, d), c + vec2(d, -d)], stroke);
+            } else {
+                // One sound wave per third of the range, like most OS volume icons.
+                let waves = 1 + (level * 2.99) as usize;
+                for wave in 0..waves {
+                    let radius = 3.0 + wave as f32 * 3.0;
+                    painter.add(Shape::line(
+                        Self::calculate_arc_points(origin, radius),
+                        stroke,
+                    ));
+                }
+            }
+        }
+        self.apply_cursor(&response, ui);
+        response.on_hover_text(if muted { "Unmute" } else { "Mute" })
+    }
+
+    // --- VOLUME SLIDER ---
+    /// Horizontal volume slider painted like the timeline; the mouse wheel also adjusts it.
+    pub fn volume_slider(&self, ui: &mut egui::Ui, level: &mut f32, muted: bool) -> egui::Response {
+        let desired_size = egui::vec2(90.0, 16.0);
+        let (rect, mut response) = ui.allocate_exact_size(desired_size, Sense::click_and_drag());
+
+        if let Some(mouse_pos) = response.interact_pointer_pos() {
+            *level = ((mouse_pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0);
+            response.mark_changed();
+        }
+
+        if response.hovered() {
+            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                // One notch of a typical wheel is ~50 points of scroll.
+                *level = (*level + scroll / 50.0 * Volume::STEP).clamp(0.0, 1.0);
+                response.mark_changed();
+            }
+        }
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter();
+            let is_hovered = response.hovered() || response.dragged();
+            let how_hovered = ui.ctx().animate_bool(response.id, is_hovered);
+
+            let thickness = egui::lerp(2.0..=4.0, how_hovered);
+
+            painter.rect_filled(
+                Rect::from_center_size(rect.center(), vec2(rect.width(), thickness)),
+                thickness / 2.0,
+                ui.visuals().extreme_bg_color,
+            );
+
+            let fill_color = if muted {
+                ui.visuals().weak_text_color()
+            } else {
+                ui.visuals().selection.bg_fill
+            };
+            let level_width = level.clamp(0.0, 1.0) * rect.width();
+            let level_rect = Rect::from_min_size(
+                egui::pos2(rect.min.x, rect.center().y - thickness / 2.0),
+                vec2(level_width, thickness),
+            );
+            painter.rect_filled(level_rect, thickness / 2.0, fill_color);
+
+            if how_hovered > 0.0 {
+                let handle_radius = egui::lerp(0.0..=6.0, how_hovered);
+                let handle_pos = egui::pos2(rect.min.x + level_width, rect.center().y);
+                painter.circle_filled(handle_pos, handle_radius, Color32::WHITE);
+            }
+        }
+        self.apply_cursor(&response, ui);
+        response.on_hover_text(format!("Volume {:.0}%", *level * 100.0))
+    }
+
+    // --- EQUALIZER BAND SLIDER ---
+    /// Vertical slider for one equalizer band, with the 0 dB line marked.
+    pub fn band_slider(
+        &self,
+        ui: &mut egui::Ui,
+        gain_db: &mut f32,
+        range: std::ops::RangeInclusive<f32>,
+    ) -> egui::Response {
+        let desired_size = egui::vec2(24.0, 110.0);
+        let (rect, mut response) = ui.allocate_exact_size(desired_size, Sense::click_and_drag());
+        let (min, max) = (*range.start(), *range.end());
+        let to_y = |value: f32| egui::remap_clamp(value, min..=max, rect.bottom()..=rect.top());
+
+        if let Some(mouse_pos) = response.interact_pointer_pos() {
+            let value = egui::remap_clamp(mouse_pos.y, rect.bottom()..=rect.top(), min..=max);
+            // Snap to half-dB steps, which is as fine as anyone can hear.
+            *gain_db = (value * 2.0).round() / 2.0;
+            response.mark_changed();
+        }
+        if response.double_clicked() {
+            *gain_db = 0.0;
+            response.mark_changed();
+        }
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter();
+            let is_hovered = response.hovered() || response.dragged();
+            let how_hovered = ui.ctx().animate_bool(response.id, is_hovered);
+
+            let thickness = egui::lerp(2.0..=4.0, how_hovered);
+            let cx = painter.round_to_pixel_center(rect.center().x);
+
+            painter.rect_filled(
+                Rect::from_center_size(Pos2::new(cx, rect.center().y), vec2(thickness, rect.height())),
+                thickness / 2.0,
+                ui.visuals().extreme_bg_color,
+            );
+
+            let zero_y = to_y(0.0);
+            painter.line_segment(
+                [Pos2::new(cx - 6.0, zero_y), Pos2::new(cx + 6.0, zero_y)],
+                Stroke::new(1.0, ui.visuals().weak_text_color()),
+            );
+
+            let value_y = to_y(*gain_db);
+            let level_rect = Rect::from_min_max(
+                Pos2::new(cx - thickness / 2.0, value_y.min(zero_y)),
+                Pos2::new(cx + thickness / 2.0, value_y.max(zero_y)),
+            );
+            painter.rect_filled(level_rect, thickness / 2.0, ui.visuals().selection.bg_fill);
+
+            let handle_radius = egui::lerp(4.0..=6.0, how_hovered);
+            let handle_color = color_util::lerp_color(
+                ui.visuals().text_color(),
+                Color32::WHITE,
+                how_hovered,
+            );
+            painter.circle_filled(Pos2::new(cx, value_y), handle_radius, handle_color);
+        }
+        self.apply_cursor(&response, ui);
+        response.on_hover_text(format!("{:+.1} dB", *gain_db))
+    }
+
+    // --- SPEED SELECTOR ---
+    /// Returns `true` when a different speed was picked.
+    pub fn show_speed_selector(&self, ui: &mut egui::Ui, speed: &mut f32) -> bool {
+        const SPEEDS: [f32; 9] = [0.5, 0.75, 1.0, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0];
+
+        let mut changed = false;
+        egui::ComboBox::from_id_salt("playback_speed")
+            .width(55.0)
+            .selected_text(format!("{}×", speed))
+            .show_ui(ui, |ui| {
+                for option in SPEEDS {
+                    changed |= ui
+                        .selectable_value(speed, option, format!("{}×", option))
+                        .changed();
+                }
+            })
+            .response
+            .on_hover_text("Playback speed (pitch is preserved)");
+        changed
+    }
+
     // --- TIMELINE SLIDER ---
     pub fn timeline_slider_with_time(
         &self,
         ui: &mut egui::Ui,
-        current_sec: &mut u64,
-        total_sec: u64,
+        current: &mut Duration,
+        total: Duration,
+        ab_loop: &mut AbLoop,
     ) -> egui::Response {
         ui.horizontal(|ui| {
             let text_color = ui.visuals().weak_text_color();
 
-            let current_time_str = self.format_time(*current_sec);
+            // Short clips get tenths so the readout visibly moves.
+            let precise = total < Self::SHORT_CLIP;
+            let current_time_str = self.format_time(*current, precise);
 
-            let total_time_str = self.format_time(total_sec);
+            let total_time_str = self.format_time(total, precise);
             let label_style = |text: String| {
                 egui::RichText::new(text)
                     .monospace()
@@ -102,7 +375,7 @@ impl MusicButtons {
 
             ui.add(egui::Label::new(label_style(current_time_str)));
 
-            let slider_res = self.timeline_slider(ui, current_sec, total_sec);
+            let slider_res = self.timeline_slider(ui, current, total, ab_loop);
 
             ui.add(egui::Label::new(label_style(total_time_str)));
 
@@ -114,12 +387,16 @@ impl MusicButtons {
     pub fn timeline_slider(
         &self,
         ui: &mut egui::Ui,
-        current_sec: &mut u64,
-        total_sec: u64,
+        current: &mut Duration,
+        total: Duration,
+        ab_loop: &mut AbLoop,
     ) -> egui::Response {
         let desired_size = egui::vec2(ui.available_width(), 16.0);
         let (rect, mut response) = ui.allocate_at_least(desired_size, Sense::click_and_drag());
 
+        // Registered after the track so the loop handles win when they overlap it.
+        self.loop_handles(ui, rect, &response, total, ab_loop);
+
         if ui.is_rect_visible(rect) {
             let painter = ui.painter();
             let is_hovered = response.hovered() || response.dragged();
@@ -129,12 +406,12 @@ impl MusicButtons {
 
             if let Some(mouse_pos) = response.interact_pointer_pos() {
                 let percentage = ((mouse_pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0);
-                *current_sec = (percentage * total_sec as f32) as u64;
+                *current = total.mul_f32(percentage);
                 response.mark_changed();
             }
 
-            let progress = if total_sec > 0 {
-                (*current_sec as f32 / total_sec as f32).clamp(0.0, 1.0)
+            let progress = if !total.is_zero() {
+                (current.as_secs_f32() / total.as_secs_f32()).clamp(0.0, 1.0)
             } else {
                 0.0
             };
@@ -145,6 +422,8 @@ impl MusicButtons {
                 ui.visuals().extreme_bg_color,
             );
 
+            self.paint_loop_region(ui, rect, total, ab_loop);
+
             let progress_width = progress * rect.width();
             let progress_rect = Rect::from_min_size(
                 egui::pos2(rect.min.x, rect.center().y - thickness / 2.0),
@@ -166,6 +445,101 @@ impl MusicButtons {
         response
     }
 
+    // --- A-B LOOP ---
+
+    pub fn show_loop_button(&self, ui: &mut egui::Ui, label: &str, active: bool) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(vec2(28.0, 25.0), Sense::click());
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter();
+            let how_hovered = ui.ctx().animate_bool(response.id, response.hovered());
+
+            self.handle_button_background(ui, &response, painter, &rect, how_hovered);
+
+            let icon_color = if active {
+                ui.visuals().selection.bg_fill
+            } else {
+                self.get_themed_icon_color(ui, &response, how_hovered)
+            };
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                label,
+                egui::FontId::monospace(12.0),
+                icon_color,
+            );
+        }
+        self.apply_cursor(&response, ui);
+        response
+    }
+
+    fn loop_handles(
+        &self,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        track: &Response,
+        total: Duration,
+        ab_loop: &mut AbLoop,
+    ) {
+        if total.is_zero() {
+            return;
+        }
+        let to_x = |pos: Duration| {
+            rect.min.x + (pos.as_secs_f32() / total.as_secs_f32()).clamp(0.0, 1.0) * rect.width()
+        };
+        let to_pos = |x: f32| total.mul_f32(((x - rect.min.x) / rect.width()).clamp(0.0, 1.0));
+
+        for is_b in [false, true] {
+            let Some(point) = (if is_b { ab_loop.b } else { ab_loop.a }) else {
+                continue;
+            };
+            let handle_rect = Rect::from_center_size(
+                Pos2::new(to_x(point), rect.center().y),
+                vec2(8.0, rect.height()),
+            );
+            let handle = ui.interact(
+                handle_rect,
+                track.id.with(if is_b { "loop_b" } else { "loop_a" }),
+                Sense::drag(),
+            );
+            if let Some(mouse_pos) = handle.interact_pointer_pos() {
+                let pos = to_pos(mouse_pos.x);
+                // Handles can't cross each other.
+                if is_b {
+                    if ab_loop.a.is_none_or(|a| pos > a) {
+                        ab_loop.b = Some(pos);
+                    }
+                } else if ab_loop.b.is_none_or(|b| pos < b) {
+                    ab_loop.a = Some(pos);
+                }
+            }
+            if handle.hovered() || handle.dragged() {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+            }
+        }
+    }
+
+    fn paint_loop_region(&self, ui: &egui::Ui, rect: Rect, total: Duration, ab_loop: &AbLoop) {
+        if total.is_zero() || ab_loop.is_empty() {
+            return;
+        }
+        let painter = ui.painter();
+        let accent = ui.visuals().selection.bg_fill;
+        let to_x = |pos: Duration| {
+            rect.min.x + (pos.as_secs_f32() / total.as_secs_f32()).clamp(0.0, 1.0) * rect.width()
+        };
+
+        if let Some((a, b)) = ab_loop.region() {
+            let band = Rect::from_x_y_ranges(to_x(a)..=to_x(b), rect.y_range());
+            painter.rect_filled(band, 2.0, accent.linear_multiply(0.25));
+        }
+        for point in [ab_loop.a, ab_loop.b].into_iter().flatten() {
+            let x = painter.round_to_pixel_center(to_x(point));
+            let marker = Rect::from_center_size(Pos2::new(x, rect.center().y), vec2(2.0, rect.height()));
+            painter.rect_filled(marker, 1.0, accent);
+        }
+    }
+
     // --- SHARED HELPERS ---
 
     fn handle_button_background(
@@ -216,18 +590,36 @@ impl MusicButtons {
         }
     }
 
+    fn get_toggle_icon_color(
+        &self,
+        ui: &egui::Ui,
+        response: &Response,
+        how_hovered: f32,
+        active: bool,
+    ) -> Color32 {
+        if active {
+            ui.visuals().selection.bg_fill
+        } else {
+            self.get_themed_icon_color(ui, response, how_hovered)
+        }
+    }
+
     fn apply_cursor(&self, response: &Response, ui: &egui::Ui) {
         if response.hovered() {
             ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
         }
     }
 
-    fn format_time(&self, seconds: u64) -> String {
+    /// `m:ss` or `h:mm:ss`; with `precise`, `m:ss.t` to the tenth of a second.
+    pub fn format_time(&self, time: Duration, precise: bool) -> String {
+        let seconds = time.as_secs();
         let h = seconds / 3600;
         let m = (seconds % 3600) / 60;
         let s = seconds % 60;
         if h > 0 {
             format!("{}:{:02}:{:02}", h, m, s)
+        } else if precise {
+            format!("{}:{:02}.{}", m, s, time.subsec_millis() / 100)
         } else {
             format!("{}:{:02}", m, s)
         }
@@ -260,6 +652,104 @@ impl MusicButtons {
         )
     }
 
+    fn calculate_skip_icon_shapes(
+        painter: &Painter,
+        rect: &Rect,
+        forward: bool,
+    ) -> (Vec<Pos2>, Rect) {
+        let h = 5.0;
+        let bar_w = 2.0;
+        let cx = painter.round_to_pixel_center(rect.center().x);
+        let cy = painter.round_to_pixel_center(rect.center().y);
+        // Mirror the "next" icon horizontally for "previous".
+        let dir = if forward { 1.0 } else { -1.0 };
+
+        let mut triangle = vec![
+            Pos2::new(cx - dir * (h + 1.0), cy - h),
+            Pos2::new(cx - dir * (h + 1.0), cy + h),
+            Pos2::new(cx + dir * (h - 1.0), cy),
+        ];
+        // Keep the winding order consistent so feathering stays on the outside.
+        if !forward {
+            triangle.reverse();
+        }
+        let bar_x = cx + dir * (h - 1.0);
+        let bar = Rect::from_min_max(
+            Pos2::new(bar_x.min(bar_x + dir * bar_w), cy - h),
+            Pos2::new(bar_x.max(bar_x + dir * bar_w), cy + h),
+        );
+        (triangle, bar)
+    }
+
+    /// Two crossing lanes, each ending in an arrowhead on the right.
+    fn calculate_shuffle_icon_shapes(painter: &Painter, rect: &Rect) -> [(Vec<Pos2>, Vec<Pos2>); 2] {
+        let cx = painter.round_to_pixel_center(rect.center().x);
+        let cy = painter.round_to_pixel_center(rect.center().y);
+        let (w, h) = (6.0, 4.0);
+
+        let lane = |from_y: f32, to_y: f32| {
+            let path = vec![
+                Pos2::new(cx - w, cy + from_y),
+                Pos2::new(cx - w / 3.0, cy + from_y),
+                Pos2::new(cx + w / 3.0, cy + to_y),
+                Pos2::new(cx + w - 1.0, cy + to_y),
+            ];
+            let tip = Pos2::new(cx + w + 2.0, cy + to_y);
+            let head = vec![tip + vec2(-3.0, -2.5), tip + vec2(-3.0, 2.5), tip];
+            (path, head)
+        };
+        [lane(-h, h), lane(h, -h)]
+    }
+
+    /// A rounded loop with an arrowhead on its top edge pointing right.
+    fn calculate_repeat_icon_shapes(painter: &Painter, rect: &Rect) -> (Vec<Pos2>, Vec<Pos2>) {
+        let cx = painter.round_to_pixel_center(rect.center().x);
+        let cy = painter.round_to_pixel_center(rect.center().y);
+        let (w, h, r) = (7.0, 4.5, 2.0);
+
+        let mut path = vec![Pos2::new(cx + w - r - 1.0, cy - h)];
+        let corners = [
+            (Pos2::new(cx + w - r, cy - h + r), -90.0f32),
+            (Pos2::new(cx + w - r, cy + h - r), 0.0),
+            (Pos2::new(cx - w + r, cy + h - r), 90.0),
+            (Pos2::new(cx - w + r, cy - h + r), 180.0),
+        ];
+        for (center, start) in corners {
+            for step in 0..=3 {
+                let angle = (start + step as f32 * 30.0).to_radians();
+                path.push(center + vec2(angle.cos(), angle.sin()) * r);
+            }
+        }
+        path.push(Pos2::new(cx - 1.0, cy - h));
+
+        let tip = Pos2::new(cx + 2.0, cy - h);
+        let head = vec![tip + vec2(-3.0, -2.5), tip + vec2(-3.0, 2.5), tip];
+        (path, head)
+    }
+
+    fn calculate_speaker_icon_shapes(painter: &Painter, rect: &Rect) -> (Rect, Vec<Pos2>) {
+        let cx = painter.round_to_pixel_center(rect.center().x) - 4.0;
+        let cy = painter.round_to_pixel_center(rect.center().y);
+        let body = Rect::from_min_max(Pos2::new(cx - 4.0, cy - 2.0), Pos2::new(cx - 1.0, cy + 2.0));
+        let cone = vec![
+            Pos2::new(cx - 1.0, cy - 2.0),
+            Pos2::new(cx - 1.0, cy + 2.0),
+            Pos2::new(cx + 3.0, cy + 5.0),
+            Pos2::new(cx + 3.0, cy - 5.0),
+        ];
+        (body, cone)
+    }
+
+    fn calculate_arc_points(origin: Pos2, radius: f32) -> Vec<Pos2> {
+        let half_angle = std::f32::consts::FRAC_PI_4;
+        (0..=8)
+            .map(|i| {
+                let angle = -half_angle + (i as f32 / 8.0) * 2.0 * half_angle;
+                origin + vec2(angle.cos(), angle.sin()) * radius
+            })
+            .collect()
+    }
+
     fn calculate_stop_icon_rect(painter: &Painter, rect: &Rect) -> Rect {
         let size = 10.0;
         let cx = painter.round_to_pixel_center(rect.center().x);
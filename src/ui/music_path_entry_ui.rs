@@ -1,15 +1,54 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
 
-use crate::services::MusicService;
+use crate::models::metadata::TrackMetadata;
+use crate::services::player::Player;
 
 use eframe::egui::{self, Response, TextureHandle};
 
+#[derive(Clone, Debug)]
+pub enum QueueRequest {
+    PlayNext(String),
+    Enqueue(String),
+}
+
 pub struct MusicPathEntryUI {
     path: String,
     directory_search_reponse: Option<Response>,
     pub request_load_music: bool,
+    pub queue_request: Option<QueueRequest>,
+    /// File whose tags the user asked to edit from the row menu, with its tags.
+    pub edit_request: Option<(PathBuf, TrackMetadata)>,
+    request_edit: Option<String>,
+    /// Tags for the edit dialog that are still being read.
+    pending_edit: Option<Receiver<Vec<(PathBuf, TrackMetadata)>>>,
+    /// Marked rows, in list order, with their tags, to edit together.
+    pub batch_request: Option<Vec<(PathBuf, TrackMetadata)>>,
+    request_batch_edit: bool,
+    /// Tags for the batch editor that are still being read.
+    pending_batch: Option<Receiver<Vec<(PathBuf, TrackMetadata)>>>,
+    /// Rows marked with Ctrl/Shift-click for batch editing.
+    marked_music: HashSet<String>,
+    /// Where the next Shift-click range starts.
+    mark_anchor: Option<usize>,
     pub music_list: Vec<String>,
     pub selected_music: Option<String>,
+    /// Row whose file the audio thread is still opening.
+    loading_music: Option<String>,
+    /// Tags of the listed files, keyed like `music_list`; filled in as the scan goes.
+    music_metadata: HashMap<String, TrackMetadata>,
+    metadata_scan: Option<Receiver<(String, TrackMetadata)>>,
+}
+
+impl Default for MusicPathEntryUI {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MusicPathEntryUI {
@@ -18,8 +57,20 @@ impl MusicPathEntryUI {
             path: String::from("music"),
             directory_search_reponse: None,
             request_load_music: false,
+            queue_request: None,
+            edit_request: None,
+            request_edit: None,
+            pending_edit: None,
+            batch_request: None,
+            request_batch_edit: false,
+            pending_batch: None,
+            marked_music: HashSet::new(),
+            mark_anchor: None,
             music_list: Vec::new(),
             selected_music: None,
+            loading_music: None,
+            music_metadata: HashMap::new(),
+            metadata_scan: None,
         }
     }
 pub fn show(&mut self, ui: &mut egui::Ui) {
@@ -40,20 +91,108 @@ pub fn show(&mut self, ui: &mut egui::Ui) {
 
         // --- Music List ---
         if !self.music_list.is_empty() {
-            ui.label(egui::RichText::new("Library").strong());
-            
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("Library").strong())
+                    .on_hover_text("Ctrl-click or Shift-click to mark several tracks");
+                if !self.marked_music.is_empty() {
+                    ui.weak(format!("{} marked", self.marked_music.len()));
+                    if ui.small_button("✖").on_hover_text("Unmark all").clicked() {
+                        self.marked_music.clear();
+                    }
+                }
+            });
+
             egui::ScrollArea::vertical()
                 .max_height(250.0) // Limits height so player is visible
                 .show(ui, |ui| {
                     for (index, music) in self.music_list.iter().enumerate() {
                         let is_selected = self.selected_music.as_ref() == Some(music);
+                        let is_marked = self.marked_music.contains(music);
+                        let is_loading = self.loading_music.as_ref() == Some(music);
                         
+                        let metadata = self.music_metadata.get(music);
+                        let label = match metadata.and_then(|m| m.title.as_ref()) {
+                            Some(title) => match metadata.and_then(TrackMetadata::artist_line) {
+                                Some(artist) => format!("🎵 {} — {}", title, artist),
+                                None => format!("🎵 {}", title),
+                            },
+                            None => format!("🎵 {}", music),
+                        };
+
                         // FIX: push_id prevents the "ID" render error
                         ui.push_id(index, |ui| {
-                            if ui.selectable_label(is_selected, format!("🎵 {}", music)).clicked() {
-                                self.selected_music = Some(music.clone());
-                                self.request_load_music = true;
+                            let row = ui
+                                .horizontal(|ui| {
+                                    let row = ui.selectable_label(is_selected || is_marked, label);
+                                    if is_loading {
+                                        ui.spinner();
+                                    }
+                                    row
+                                })
+                                .inner;
+                            let row = match metadata {
+                                Some(metadata) if !metadata.details().is_empty() => {
+                                    row.on_hover_ui(|ui| {
+                                        ui.weak(music);
+                                        for (label, value) in metadata.details() {
+                                            ui.label(format!("{}: {}", label, value));
+                                        }
+                                    })
+                                }
+                                _ => row,
+                            };
+                            if row.clicked() {
+                                let modifiers = ui.input(|i| i.modifiers);
+                                if modifiers.command {
+                                    if !self.marked_music.remove(music) {
+                                        self.marked_music.insert(music.clone());
+                                    }
+                                    self.mark_anchor = Some(index);
+                                } else if modifiers.shift {
+                                    let anchor = self.mark_anchor.unwrap_or(index);
+                                    let range = anchor.min(index)..=anchor.max(index);
+                                    self.marked_music.extend(self.music_list[range].iter().cloned());
+                                } else {
+                                    self.marked_music.clear();
+                                    self.mark_anchor = Some(index);
+                                    self.selected_music = Some(music.clone());
+                                    self.request_load_music = true;
+                                }
                             }
+                            row.context_menu(|ui| {
+                                if ui.button("⏭ Play next").clicked() {
+                                    self.queue_request = Some(QueueRequest::PlayNext(music.clone()));
+                                    ui.close();
+                                }
+                                if ui.button("➕ Add to queue").clicked() {
+                                    self.queue_request = Some(QueueRequest::Enqueue(music.clone()));
+                                    ui.close();
+                                }
+                                ui.separator();
+                                let album = metadata.and_then(|m| m.album.as_ref());
+                                if let Some(album) = album
+                                    && ui.button("☑ Mark album").clicked()
+                                {
+                                    let tracks = self.music_list.iter().filter(|other| {
+                                        self.music_metadata
+                                            .get(*other)
+                                            .and_then(|m| m.album.as_ref())
+                                            == Some(album)
+                                    });
+                                    self.marked_music = tracks.cloned().collect();
+                                    ui.close();
+                                }
+                                if is_marked && self.marked_music.len() > 1 {
+                                    let label = format!("✏ Edit tags of {} tracks…", self.marked_music.len());
+                                    if ui.button(label).clicked() {
+                                        self.request_batch_edit = true;
+                                        ui.close();
+                                    }
+                                } else if ui.button("✏ Edit tags…").clicked() {
+                                    self.request_edit = Some(music.clone());
+                                    ui.close();
+                                }
+                            });
                         });
                     }
                 });
@@ -71,10 +210,8 @@ pub fn show(&mut self, ui: &mut egui::Ui) {
             println!("Path is not directory");
             return None;
         }
-        println!("Hello");
         let list: Vec<String> = fs::read_dir(path)
             .unwrap()
-            .into_iter()
             .map(|entry| match entry {
                 Ok(entry) => {
                     if let Some(name) = entry.file_name().to_str() {
@@ -97,36 +234,153 @@ pub fn show(&mut self, ui: &mut egui::Ui) {
 pub fn on_submit(
     &mut self,
     _ctx: &egui::Context,
-    service: &mut MusicService,
+    player: &mut Player,
     _texture_handle: &mut Option<TextureHandle>,
 ) {
     // 1. Handle Directory Searching
-    if let Some(response) = &self.directory_search_reponse {
-        if response.clicked() {
-            println!("Hello");
-            if let Some(music_files) = self.music_files_from_dir() {
-                self.music_list = music_files;
-            }
-        }
+    if let Some(response) = &self.directory_search_reponse
+        && response.clicked()
+        && let Some(music_files) = self.music_files_from_dir()
+    {
+        self.music_list = music_files;
+        self.scan_metadata();
+    }
+    if let Some(scan) = &self.metadata_scan {
+        self.music_metadata.extend(scan.try_iter());
     }
 
     // 2. Handle File Loading
-    // This must check the flag set by the 'show' method's click event
-    if self.request_load_music {
-        if let Some(music_file) = &self.selected_music {
-            let music_path = PathBuf::from(&self.path).join(music_file);
-            
-            match service.open(&music_path) {
-                Ok(_) => {
-                    println!("Successfully loaded: {:?}", music_path);
-                    self.request_load_music = false; // Reset ONLY after successful load
-                }
-                Err(e) => {
-                    eprintln!("Failed to load music: {:?}", e);
-                    self.request_load_music = false; // Reset even on error to stop loop
+    // This must check the flag set by the 'show' method's click event.
+    // The whole library listing becomes the play queue, starting at the clicked row.
+    if self.request_load_music
+        && let Some(music_file) = &self.selected_music
+    {
+        let index = self
+            .music_list
+            .iter()
+            .position(|m| m == music_file)
+            .unwrap_or(0);
+        let tracks = self.music_list.iter().map(|m| self.music_path(m)).collect();
+
+        // Opens on the audio thread; failures come back through the player.
+        player.play_tracks(tracks, index);
+        self.request_load_music = false;
+    }
+
+    // 3. Handle queue edits from the row context menu
+    if let Some(request) = self.queue_request.take() {
+        match request {
+            QueueRequest::PlayNext(music) => player.insert_next(self.music_path(&music)),
+            QueueRequest::Enqueue(music) => player.enqueue(self.music_path(&music)),
+        }
+    }
+
+    // 4. Hand the marked rows over for batch editing, in list order
+    if self.request_batch_edit {
+        self.request_batch_edit = false;
+        let marked = self
+            .music_list
+            .iter()
+            .filter(|music| self.marked_music.contains(*music));
+        self.pending_batch = Some(self.read_tags(marked));
+    }
+    if let Some(pending) = &self.pending_batch
+        && let Ok(tracks) = pending.try_recv()
+    {
+        self.pending_batch = None;
+        self.batch_request = Some(tracks);
+    }
+
+    // 5. Open the tag editor once the row's tags are at hand
+    if let Some(music) = self.request_edit.take() {
+        self.pending_edit = Some(self.read_tags([&music]));
+    }
+    if let Some(pending) = &self.pending_edit
+        && let Ok(tracks) = pending.try_recv()
+    {
+        self.pending_edit = None;
+        self.edit_request = tracks.into_iter().next();
+    }
+
+    // 6. Spin on the row that is still opening
+    self.loading_music = player
+        .loading()
+        .and_then(|path| path.file_name())
+        .and_then(|name| name.to_str())
+        .map(str::to_owned);
+
+    // 7. Keep the highlighted row on whatever the queue is playing
+    if self.loading_music.is_none()
+        && let Some(current) = &player.view().current_path
+        && let Some(name) = current.file_name().and_then(|n| n.to_str())
+        && self.selected_music.as_deref() != Some(name)
+        && self.music_list.iter().any(|m| m == name)
+    {
+        self.selected_music = Some(name.to_string());
+    }
+}
+
+    /// Reads the listed files' tags on a background thread; a large folder would
+    /// otherwise stall the UI. Starting a new scan abandons the previous one.
+    fn scan_metadata(&mut self) {
+        let (sender, receiver) = mpsc::channel();
+        let files: Vec<(String, PathBuf)> = self
+            .music_list
+            .iter()
+            .map(|music| (music.clone(), self.music_path(music)))
+            .collect();
+        thread::spawn(move || {
+            for (music, path) in files {
+                if sender.send((music, TrackMetadata::read_from_path(path))).is_err() {
+                    return;
                 }
             }
+        });
+        self.music_metadata.clear();
+        self.marked_music.clear();
+        self.mark_anchor = None;
+        self.metadata_scan = Some(receiver);
+    }
+
+    /// The tags of `rows`, in order, for an edit dialog. Rows the scan hasn't
+    /// reached yet are read on a background thread so the UI doesn't stall.
+    fn read_tags<'a>(
+        &self,
+        rows: impl IntoIterator<Item = &'a String>,
+    ) -> Receiver<Vec<(PathBuf, TrackMetadata)>> {
+        let (sender, receiver) = mpsc::channel();
+        let tracks: Vec<(PathBuf, Option<TrackMetadata>)> = rows
+            .into_iter()
+            .map(|music| (self.music_path(music), self.music_metadata.get(music).cloned()))
+            .collect();
+        let scanned = tracks.iter().all(|(_, metadata)| metadata.is_some());
+        let read = move || {
+            let tracks = tracks
+                .into_iter()
+                .map(|(path, metadata)| {
+                    let metadata =
+                        metadata.unwrap_or_else(|| TrackMetadata::read_from_path(&path));
+                    (path, metadata)
+                })
+                .collect();
+            let _ = sender.send(tracks);
+        };
+        if scanned {
+            read();
+        } else {
+            thread::spawn(read);
         }
+        receiver
+    }
+
+    /// Shows freshly written tags on the row for `path`, if it is listed.
+    pub fn update_metadata(&mut self, path: &Path, metadata: TrackMetadata) {
+        if let Some(music) = self.music_list.iter().find(|music| self.music_path(music) == path) {
+            self.music_metadata.insert(music.clone(), metadata);
+        }
+    }
+
+    fn music_path(&self, music_file: &str) -> PathBuf {
+        PathBuf::from(&self.path).join(music_file)
     }
-}
 }
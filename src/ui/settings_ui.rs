@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use eframe::egui;
+
+use crate::audio::{fade::FadeCurve, transport};
+use crate::services::{
+    crossfade::CrossfadeSettings,
+    output,
+    player::Player,
+    replay_gain::{ReplayGainMode, ReplayGainSettings},
+    resume_points::ResumeSettings,
+};
+
+pub struct SettingsUI {
+    /// Listed lazily and on demand; enumerating devices can take a while.
+    output_devices: Option<Vec<String>>,
+}
+
+impl Default for SettingsUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SettingsUI {
+    pub fn new() -> Self {
+        Self {
+            output_devices: None,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, player: &mut Player) {
+        egui::CollapsingHeader::new("⚙ Settings")
+            .id_salt("settings")
+            .show(ui, |ui| {
+                self.show_output_device(ui, player);
+                self.show_crossfade(ui, player);
+                self.show_transport_ramp(ui, player);
+                self.show_replay_gain(ui, player);
+                self.show_resume_points(ui, player);
+            });
+    }
+
+    fn show_output_device(&mut self, ui: &mut egui::Ui, player: &mut Player) {
+        let devices = self
+            .output_devices
+            .get_or_insert_with(output::output_device_names);
+        let mut selected = player.view().output_device.clone();
+        let mut refresh = false;
+
+        ui.horizontal(|ui| {
+            ui.label("Output");
+            egui::ComboBox::from_id_salt("output_device")
+                .selected_text(selected.as_deref().unwrap_or("System default"))
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut selected, None, "System default");
+                    for name in devices.iter() {
+                        ui.selectable_value(&mut selected, Some(name.clone()), name);
+                    }
+                });
+            if ui.small_button("⟳").on_hover_text("Refresh devices").clicked() {
+                refresh = true;
+            }
+        });
+        if refresh {
+            self.output_devices = None;
+        }
+
+        // Make a fallback visible instead of silently playing elsewhere.
+        let view = player.view();
+        if let Some(preferred) = &view.output_device
+            && let Some(active) = &view.active_output_device
+            && active != preferred
+        {
+            ui.weak(format!("{} is unavailable, playing on {}", preferred, active));
+        }
+
+        if selected != player.view().output_device {
+            player.set_output_device(selected);
+        }
+    }
+
+    fn show_crossfade(&mut self, ui: &mut egui::Ui, player: &mut Player) {
+        let mut settings = player.view().crossfade;
+        let mut secs = settings.duration.as_secs_f32();
+
+        ui.horizontal(|ui| {
+            ui.label("Crossfade");
+            let max = CrossfadeSettings::MAX_DURATION.as_secs_f32();
+            ui.add(
+                egui::Slider::new(&mut secs, 0.0..=max)
+                    .step_by(0.5)
+                    .suffix(" s"),
+            )
+            .on_hover_text("0 s plays tracks back to back without a gap");
+
+            ui.add_enabled_ui(secs > 0.0, |ui| {
+                egui::ComboBox::from_id_salt("crossfade_curve")
+                    .selected_text(settings.curve.label())
+                    .show_ui(ui, |ui| {
+                        for curve in FadeCurve::ALL {
+                            ui.selectable_value(&mut settings.curve, curve, curve.label());
+                        }
+                    });
+            });
+        });
+
+        settings.duration = Duration::from_secs_f32(secs);
+        if settings != player.view().crossfade {
+            player.set_crossfade(settings);
+        }
+    }
+
+    fn show_transport_ramp(&mut self, ui: &mut egui::Ui, player: &mut Player) {
+        let mut millis = player.view().transport_ramp.as_millis() as u32;
+        let max = transport::MAX_RAMP.as_millis() as u32;
+
+        ui.horizontal(|ui| {
+            ui.label("Transport fade");
+            ui.add(
+                egui::Slider::new(&mut millis, 0..=max)
+                    .step_by(5.0)
+                    .suffix(" ms"),
+            )
+            .on_hover_text("Ramp around pause, play, stop and seek so they don't click");
+        });
+
+        let ramp = Duration::from_millis(u64::from(millis));
+        if ramp != player.view().transport_ramp {
+            player.set_transport_ramp(ramp);
+        }
+    }
+
+    fn show_replay_gain(&mut self, ui: &mut egui::Ui, player: &mut Player) {
+        let mut settings = player.view().replay_gain;
+
+        ui.horizontal(|ui| {
+            ui.label("ReplayGain");
+            egui::ComboBox::from_id_salt("replay_gain_mode")
+                .selected_text(settings.mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in ReplayGainMode::ALL {
+                        ui.selectable_value(&mut settings.mode, mode, mode.label());
+                    }
+                });
+
+            ui.add_enabled_ui(settings.mode != ReplayGainMode::Off, |ui| {
+                ui.label("Pre-amp");
+                ui.add(
+                    egui::Slider::new(
+                        &mut settings.preamp_db,
+                        ReplayGainSettings::PREAMP_RANGE_DB,
+                    )
+                    .step_by(0.5)
+                    .suffix(" dB"),
+                );
+            });
+        });
+
+        if settings != player.view().replay_gain {
+            player.set_replay_gain(settings);
+        }
+    }
+
+    fn show_resume_points(&mut self, ui: &mut egui::Ui, player: &mut Player) {
+        let mut settings = player.view().resume_settings;
+        let mut minutes = settings.min_length.as_secs_f32() / 60.0;
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.enabled, "Remember position")
+                .on_hover_text("Offer to continue long tracks where you left off");
+            ui.add_enabled_ui(settings.enabled, |ui| {
+                ui.label("for tracks over");
+                let max = ResumeSettings::MAX_MIN_LENGTH.as_secs_f32() / 60.0;
+                ui.add(
+                    egui::Slider::new(&mut minutes, 0.0..=max)
+                        .step_by(1.0)
+                        .suffix(" min"),
+                );
+            });
+        });
+
+        settings.min_length = Duration::from_secs_f32(minutes * 60.0);
+        if settings != player.view().resume_settings {
+            player.set_resume_settings(settings);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use eframe::egui;
+
+use crate::services::{
+    player::Player,
+    sleep_timer::{SleepDeadline, SleepMode},
+};
+use crate::ui::music_buttons::MusicButtons;
+
+pub struct SleepTimerUI {
+    minutes: u32,
+    tracks: u32,
+}
+
+impl Default for SleepTimerUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SleepTimerUI {
+    pub fn new() -> Self {
+        Self {
+            minutes: 30,
+            tracks: 3,
+        }
+    }
+
+    /// The countdown with extend/cancel buttons while running, otherwise a menu to start one.
+    pub fn show(&mut self, ui: &mut egui::Ui, player: &mut Player, buttons: &MusicButtons) {
+        let Some(timer) = player.view().sleep_timer else {
+            self.show_menu(ui, player);
+            return;
+        };
+
+        let remaining = match (timer.deadline(), player.view().sleep_timer_remaining) {
+            (SleepDeadline::Tracks(tracks), _) if tracks > 1 => format!("{} tracks", tracks),
+            (SleepDeadline::Tracks(_), None) => "end of track".to_owned(),
+            (_, Some(remaining)) => buttons.format_time(remaining, false),
+            (SleepDeadline::At(_), None) => String::new(),
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(format!("😴 {}", remaining))
+                .on_hover_text("Playback fades out and pauses when the sleep timer runs out");
+            let extend_hint = match timer.deadline() {
+                SleepDeadline::At(_) => "Add 5 minutes",
+                SleepDeadline::Tracks(_) => "Add one more track",
+            };
+            if ui.small_button("➕").on_hover_text(extend_hint).clicked() {
+                player.extend_sleep_timer();
+            }
+            if ui.small_button("✖").on_hover_text("Cancel sleep timer").clicked() {
+                player.cancel_sleep_timer();
+            }
+        });
+    }
+
+    fn show_menu(&mut self, ui: &mut egui::Ui, player: &mut Player) {
+        ui.menu_button("😴", |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut self.minutes)
+                        .range(1..=600)
+                        .suffix(" min"),
+                );
+                if ui.button("Start").clicked() {
+                    let after = Duration::from_secs(u64::from(self.minutes) * 60);
+                    player.set_sleep_timer(SleepMode::After(after));
+                    ui.close();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut self.tracks)
+                        .range(1..=99)
+                        .suffix(" tracks"),
+                );
+                if ui.button("Start").clicked() {
+                    player.set_sleep_timer(SleepMode::AfterTracks(self.tracks));
+                    ui.close();
+                }
+            });
+            if ui.button("At end of this track").clicked() {
+                player.set_sleep_timer(SleepMode::EndOfTrack);
+                ui.close();
+            }
+        })
+        .response
+        .on_hover_text("Sleep timer");
+    }
+}
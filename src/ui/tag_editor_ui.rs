@@ -0,0 +1,170 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use eframe::egui;
+
+use crate::models::{
+    metadata::TrackMetadata,
+    tags::{
+        TrackTags,
+        writer::{self, TagChanges},
+    },
+};
+use crate::services::player::Player;
+
+struct EditSession {
+    path: PathBuf,
+    /// The tags as they were when the dialog opened.
+    original: TrackMetadata,
+    fields: TagChanges,
+    saving: Option<Receiver<io::Result<TrackTags>>>,
+    error: Option<String>,
+}
+
+impl EditSession {
+    /// Only the fields that were actually edited, so untouched frames stay as they are.
+    fn changes(&self) -> TagChanges {
+        self.fields.relative_to(&self.original)
+    }
+}
+
+/// Dialog for fixing one track's tags; saving writes them back to the file.
+pub struct TagEditorUI {
+    session: Option<EditSession>,
+}
+
+impl Default for TagEditorUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TagEditorUI {
+    pub fn new() -> Self {
+        Self { session: None }
+    }
+
+    /// Opens the dialog on `path`, replacing any edit that hasn't been saved.
+    pub fn open(&mut self, path: PathBuf, metadata: &TrackMetadata) {
+        self.session = Some(EditSession {
+            path,
+            original: metadata.clone(),
+            fields: writer::editable_fields(metadata),
+            saving: None,
+            error: None,
+        });
+    }
+
+    /// Shows the dialog while a track is being edited. Returns the path and fresh
+    /// tags once a save has landed on disk, for the library to pick up.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        player: &mut Player,
+    ) -> Option<(PathBuf, TrackTags)> {
+        let session = self.session.as_mut()?;
+
+        if let Some(saving) = &session.saving
+            && let Ok(result) = saving.try_recv()
+        {
+            session.saving = None;
+            match result {
+                Ok(tags) => {
+                    let path = session.path.clone();
+                    player.update_tags(path.clone(), tags.clone());
+                    self.session = None;
+                    return Some((path, tags));
+                }
+                Err(e) => session.error = Some(e.to_string()),
+            }
+        }
+
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+        let title = session
+            .path
+            .file_name()
+            .map(|name| format!("✏ Edit tags — {}", name.to_string_lossy()))
+            .unwrap_or_else(|| "✏ Edit tags".to_owned());
+
+        egui::Window::new(title)
+            .id(egui::Id::new("tag_editor"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let saving = session.saving.is_some();
+                ui.add_enabled_ui(!saving, |ui| {
+                    egui::Grid::new("tag_editor_fields")
+                        .num_columns(2)
+                        .spacing([10.0, 6.0])
+                        .show(ui, |ui| {
+                            let fields = &mut session.fields;
+                            field(ui, "Title", &mut fields.title, "");
+                            field(ui, "Artist", &mut fields.artist, "Separate several with ;");
+                            field(ui, "Album", &mut fields.album, "");
+                            field(ui, "Album artist", &mut fields.album_artist, "");
+                            field(ui, "Track", &mut fields.track, "3 or 3/12");
+                            field(ui, "Genre", &mut fields.genre, "");
+                            field(ui, "Year", &mut fields.year, "");
+
+                            ui.label("Comment");
+                            let comment = fields.comment.get_or_insert_with(String::new);
+                            ui.add(egui::TextEdit::multiline(comment).desired_rows(3));
+                            ui.end_row();
+                        });
+                });
+
+                if let Some(error) = &session.error {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {}", error));
+                }
+
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    let changes = session.changes();
+                    save = ui
+                        .add_enabled(!saving && !changes.is_empty(), egui::Button::new("💾 Save"))
+                        .clicked();
+                    cancel = ui
+                        .add_enabled(!saving, egui::Button::new("Cancel"))
+                        .clicked();
+                    if saving {
+                        ui.spinner();
+                    }
+                });
+            });
+
+        if save {
+            let changes = session.changes();
+            match changes.validate() {
+                Ok(()) => {
+                    // Rewriting copies the whole file; keep that off the UI thread.
+                    let (sender, receiver) = mpsc::channel();
+                    let path = session.path.clone();
+                    thread::spawn(move || {
+                        let _ = sender.send(writer::write_to_path(path, &changes));
+                    });
+                    session.saving = Some(receiver);
+                    session.error = None;
+                }
+                Err(e) => session.error = Some(e.to_string()),
+            }
+        }
+        // Closing waits for a save in flight, so its result still reaches the library.
+        if (!open || cancel) && session.saving.is_none() {
+            self.session = None;
+        }
+        None
+    }
+}
+
+fn field(ui: &mut egui::Ui, label: &str, value: &mut Option<String>, hint: &str) {
+    ui.label(label);
+    ui.add(egui::TextEdit::singleline(value.get_or_insert_with(String::new)).hint_text(hint));
+    ui.end_row();
+}
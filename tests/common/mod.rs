@@ -0,0 +1,40 @@
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// A fresh directory per test, so tests running in parallel never share fixtures.
+/// It is deleted with everything in it when dropped.
+pub struct FixtureDir(PathBuf);
+
+impl Deref for FixtureDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for FixtureDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for FixtureDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn fixture_dir(test: &str) -> FixtureDir {
+    // Directly under the temp dir: a shared parent would have to be removed by
+    // whichever test finishes last, racing the others creating theirs.
+    let dir = std::env::temp_dir().join(format!(
+        "music_player_tests_{}_{}",
+        std::process::id(),
+        test
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    FixtureDir(dir)
+}
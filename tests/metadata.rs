@@ -0,0 +1,679 @@
+mod common;
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use id3::{
+    Frame, Tag, TagLike, Version,
+    frame::{Comment, Timestamp},
+};
+use music_player::models::{
+    tags::{
+        batch::BatchEdit,
+        picture::PictureKind,
+        writer::{self, FieldChange, TagChanges},
+    },
+    track::Track,
+};
+
+use common::fixture_dir;
+
+/// An otherwise empty file carrying `tag`, which is all `Track::new` looks at.
+fn tagged_file(dir: &Path, name: &str, tag: &Tag) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, []).unwrap();
+    tag.write_to_path(&path, Version::Id3v24).unwrap();
+    path
+}
+
+const COVER: &[u8] = b"\x89PNG\r\n\x1a\nnot really a png";
+
+fn vorbis_comment_block(comments: &[&str]) -> Vec<u8> {
+    let vendor = b"test";
+    let mut block = Vec::new();
+    block.extend((vendor.len() as u32).to_le_bytes());
+    block.extend(vendor);
+    block.extend((comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        block.extend((comment.len() as u32).to_le_bytes());
+        block.extend(comment.as_bytes());
+    }
+    block
+}
+
+fn flac_picture_block(picture_type: u32, data: &[u8]) -> Vec<u8> {
+    let mut block = Vec::new();
+    block.extend(picture_type.to_be_bytes());
+    block.extend(9u32.to_be_bytes());
+    block.extend(b"image/png");
+    block.extend(5u32.to_be_bytes());
+    block.extend(b"Cover");
+    block.extend([0u8; 16]);
+    block.extend((data.len() as u32).to_be_bytes());
+    block.extend(data);
+    block
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// One Ogg page carrying `packets`, each shorter than 255 bytes times the lacing.
+fn ogg_page(packets: &[&[u8]]) -> Vec<u8> {
+    let mut lacing = Vec::new();
+    for packet in packets {
+        lacing.extend(std::iter::repeat_n(255u8, packet.len() / 255));
+        lacing.push((packet.len() % 255) as u8);
+    }
+    let mut page = b"OggS".to_vec();
+    page.extend([0u8; 22]);
+    page.push(lacing.len() as u8);
+    page.extend(lacing);
+    for packet in packets {
+        page.extend(*packet);
+    }
+    page
+}
+
+/// "fLaC", then `(type, body)` metadata blocks with the last one flagged.
+fn flac_file(blocks: &[(u8, &[u8])], audio: &[u8]) -> Vec<u8> {
+    let mut file = b"fLaC".to_vec();
+    for (i, (block_type, body)) in blocks.iter().enumerate() {
+        let last = if i + 1 == blocks.len() { 0x80 } else { 0 };
+        let len = (body.len() as u32).to_be_bytes();
+        file.extend([last | block_type, len[1], len[2], len[3]]);
+        file.extend(*body);
+    }
+    file.extend(audio);
+    file
+}
+
+fn mp4_atom(name: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut atom = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+    atom.extend(name);
+    atom.extend(body);
+    atom
+}
+
+fn mp4_data(kind: u32, payload: &[u8]) -> Vec<u8> {
+    let mut body = kind.to_be_bytes().to_vec();
+    body.extend([0u8; 4]);
+    body.extend(payload);
+    mp4_atom(b"data", &body)
+}
+
+fn ape_item(key: &str, flags: u32, value: &[u8]) -> Vec<u8> {
+    let mut item = (value.len() as u32).to_le_bytes().to_vec();
+    item.extend(flags.to_le_bytes());
+    item.extend(key.as_bytes());
+    item.push(0);
+    item.extend(value);
+    item
+}
+
+#[test]
+fn id3_fields_fill_the_metadata() {
+    let dir = fixture_dir("id3_fields_fill_the_metadata");
+    let mut tag = Tag::new();
+    tag.set_title("Paranoid Android");
+    tag.set_text_values("TPE1", ["Radiohead", "Guest"]);
+    tag.set_album("OK Computer");
+    tag.set_album_artist("Radiohead");
+    tag.set_track(2);
+    tag.set_total_tracks(12);
+    tag.set_disc(1);
+    tag.set_total_discs(1);
+    tag.set_date_recorded(Timestamp {
+        year: 1997,
+        month: Some(5),
+        day: Some(21),
+        hour: None,
+        minute: None,
+        second: None,
+    });
+    tag.set_genre("(17)");
+    tag.add_frame(Frame::text("TCOM", "Thom Yorke"));
+    tag.add_frame(Comment {
+        lang: "eng".into(),
+        description: String::new(),
+        text: "Single".into(),
+    });
+    tag.set_duration(383_000);
+    let path = tagged_file(&dir, "02 - track.mp3", &tag);
+
+    let track = Track::new(&path).unwrap();
+    let metadata = track.metadata();
+    assert_eq!(track.name(), "Paranoid Android");
+    assert_eq!(track.artist(), "Radiohead, Guest");
+    assert_eq!(metadata.album.as_deref(), Some("OK Computer"));
+    assert_eq!(metadata.album_artist.as_deref(), Some("Radiohead"));
+    assert_eq!(metadata.track_position().as_deref(), Some("2/12"));
+    assert_eq!(
+        (metadata.disc_number, metadata.disc_total),
+        (Some(1), Some(1))
+    );
+    assert_eq!(metadata.year, Some(1997));
+    assert_eq!(metadata.date.as_deref(), Some("1997-05-21"));
+    assert_eq!(metadata.genre.as_deref(), Some("Rock"));
+    assert_eq!(metadata.composer.as_deref(), Some("Thom Yorke"));
+    assert_eq!(metadata.comment.as_deref(), Some("Single"));
+    assert_eq!(metadata.duration, Some(Duration::from_secs(383)));
+    assert_eq!(metadata.album_line().as_deref(), Some("OK Computer (1997)"));
+}
+
+#[test]
+fn missing_tags_fall_back_sensibly() {
+    let dir = fixture_dir("missing_tags_fall_back_sensibly");
+
+    let untagged = dir.join("Some Recording.mp3");
+    std::fs::write(&untagged, []).unwrap();
+    let track = Track::new(&untagged).unwrap();
+    assert_eq!(track.name(), "Some Recording");
+    assert_eq!(track.artist(), "No named artist");
+    assert!(track.metadata().details().is_empty());
+
+    // Blank frames count as missing, and the album artist stands in for the artist.
+    let mut tag = Tag::new();
+    tag.set_title("  ");
+    tag.set_album_artist("Various Artists");
+    let path = tagged_file(&dir, "Compilation Track.mp3", &tag);
+    let track = Track::new(&path).unwrap();
+    assert_eq!(track.name(), "Compilation Track");
+    assert_eq!(track.artist(), "Various Artists");
+
+    // The placeholder track the service starts with has no file name at all.
+    assert_eq!(Track::new("").unwrap().name(), "Not named song");
+}
+
+#[test]
+fn flac_and_ogg_comments_fill_the_metadata() {
+    let dir = fixture_dir("flac_and_ogg_comments_fill_the_metadata");
+    let comments = vorbis_comment_block(&[
+        "TITLE=Hyperballad",
+        "ARTIST=Björk",
+        "album=Post",
+        "TRACKNUMBER=3/11",
+        "DISCNUMBER=1",
+        "DISCTOTAL=1",
+        "DATE=1995-06-13",
+        "GENRE=Electronic",
+        "REPLAYGAIN_TRACK_GAIN=-4.20 dB",
+    ]);
+    let picture = flac_picture_block(3, COVER);
+    let flac = flac_file(&[(4, &comments), (6, &picture)], &[]);
+    let path = dir.join("track.flac");
+    std::fs::write(&path, flac).unwrap();
+
+    let track = Track::new(&path).unwrap();
+    let metadata = track.metadata();
+    assert_eq!(track.name(), "Hyperballad");
+    assert_eq!(track.artist(), "Björk");
+    assert_eq!(metadata.album_line().as_deref(), Some("Post (1995)"));
+    assert_eq!(metadata.track_position().as_deref(), Some("3/11"));
+    assert_eq!(
+        (metadata.disc_number, metadata.disc_total),
+        (Some(1), Some(1))
+    );
+    assert_eq!(metadata.date.as_deref(), Some("1995-06-13"));
+    assert_eq!(metadata.genre.as_deref(), Some("Electronic"));
+    assert_eq!(track.replay_gain().track_gain, Some(-4.2));
+    assert_eq!(track.pictures()[0].mime_type, "image/png");
+    assert_eq!(track.pictures()[0].description, "Cover");
+    assert_eq!(track.extract_img_bytes().unwrap(), COVER);
+
+    // Opus keeps its cover as a base64 FLAC picture block inside the comments.
+    let block_picture = format!("METADATA_BLOCK_PICTURE={}", base64(&picture));
+    let mut opus_tags = b"OpusTags".to_vec();
+    opus_tags.extend(vorbis_comment_block(&["TITLE=Isobel", &block_picture]));
+    let ogg = ogg_page(&[b"OpusHead", &opus_tags]);
+    let path = dir.join("track.opus");
+    std::fs::write(&path, ogg).unwrap();
+
+    let track = Track::new(&path).unwrap();
+    assert_eq!(track.name(), "Isobel");
+    assert_eq!(track.extract_img_bytes().unwrap(), COVER);
+    assert_eq!(
+        track.metadata().details(),
+        vec![("Title", "Isobel".to_owned())]
+    );
+}
+
+#[test]
+fn mp4_atoms_fill_the_metadata() {
+    let dir = fixture_dir("mp4_atoms_fill_the_metadata");
+    let mut freeform = mp4_atom(b"mean", b"\0\0\0\0com.apple.iTunes");
+    freeform.extend(mp4_atom(b"name", b"\0\0\0\0replaygain_track_gain"));
+    freeform.extend(mp4_data(1, b"-7.5 dB"));
+    let ilst = [
+        mp4_atom(b"\xa9nam", &mp4_data(1, b"Teardrop")),
+        mp4_atom(b"\xa9ART", &mp4_data(1, b"Massive Attack")),
+        mp4_atom(b"\xa9alb", &mp4_data(1, b"Mezzanine")),
+        mp4_atom(b"\xa9day", &mp4_data(1, b"1998-04-20T07:00:00Z")),
+        mp4_atom(b"trkn", &mp4_data(0, &[0, 0, 0, 3, 0, 11, 0, 0])),
+        mp4_atom(b"gnre", &mp4_data(0, &[0, 14])),
+        mp4_atom(b"covr", &mp4_data(14, COVER)),
+        mp4_atom(b"----", &freeform),
+    ]
+    .concat();
+    let mut meta = vec![0u8; 4];
+    meta.extend(mp4_atom(b"hdlr", &[0u8; 25]));
+    meta.extend(mp4_atom(b"ilst", &ilst));
+    let moov = mp4_atom(b"moov", &mp4_atom(b"udta", &mp4_atom(b"meta", &meta)));
+    // Media data first, as in files that were never optimised for streaming.
+    let file = [
+        mp4_atom(b"ftyp", b"M4A \0\0\0\0"),
+        mp4_atom(b"mdat", &[0u8; 1024]),
+        moov,
+    ]
+    .concat();
+    let path = dir.join("track.m4a");
+    std::fs::write(&path, file).unwrap();
+
+    let track = Track::new(&path).unwrap();
+    let metadata = track.metadata();
+    assert_eq!(track.name(), "Teardrop");
+    assert_eq!(track.artist(), "Massive Attack");
+    assert_eq!(metadata.album_line().as_deref(), Some("Mezzanine (1998)"));
+    assert_eq!(metadata.date.as_deref(), Some("1998-04-20"));
+    assert_eq!(metadata.track_position().as_deref(), Some("3/11"));
+    // gnre is an ID3v1 index plus one: 13 is "Pop".
+    assert_eq!(metadata.genre.as_deref(), Some("Pop"));
+    assert_eq!(track.replay_gain().track_gain, Some(-7.5));
+    assert_eq!(track.extract_img_bytes().unwrap(), COVER);
+}
+
+#[test]
+fn ape_tags_fill_the_metadata() {
+    let dir = fixture_dir("ape_tags_fill_the_metadata");
+    let mut cover = b"cover.png\0".to_vec();
+    cover.extend(COVER);
+    let items = [
+        ape_item("Title", 0, b"Windowlicker"),
+        ape_item("Artist", 0, b"Aphex Twin\0Guest"),
+        ape_item("Album Artist", 0, b"Aphex Twin"),
+        ape_item("Track", 0, b"1/3"),
+        ape_item("Year", 0, b"1999"),
+        ape_item("Cover Art (Back)", 2, b"back.png\0\x89PNG"),
+        ape_item("Cover Art (Front)", 2, &cover),
+    ];
+    let body = items.concat();
+    let mut footer = b"APETAGEX".to_vec();
+    footer.extend(2000u32.to_le_bytes());
+    footer.extend(((body.len() + 32) as u32).to_le_bytes());
+    footer.extend((items.len() as u32).to_le_bytes());
+    footer.extend([0u8; 12]);
+    // Audio, the APEv2 tag, then an ID3v1 trailer.
+    let mut id3v1 = b"TAG".to_vec();
+    id3v1.resize(128, 0);
+    let file = [b"MAC \0\0\0\0".to_vec(), body, footer, id3v1].concat();
+    let path = dir.join("track.ape");
+    std::fs::write(&path, file).unwrap();
+
+    let track = Track::new(&path).unwrap();
+    let metadata = track.metadata();
+    assert_eq!(track.name(), "Windowlicker");
+    assert_eq!(track.artist(), "Aphex Twin, Guest");
+    assert_eq!(metadata.album_artist.as_deref(), Some("Aphex Twin"));
+    assert_eq!(metadata.track_position().as_deref(), Some("1/3"));
+    assert_eq!(
+        (metadata.year, metadata.date.as_deref()),
+        (Some(1999), None)
+    );
+    assert_eq!(track.pictures().len(), 2);
+    assert_eq!(track.pictures()[0].kind, PictureKind::BackCover);
+    assert_eq!(track.extract_img_bytes().unwrap(), COVER);
+}
+
+#[test]
+fn edited_id3_tags_are_written_back() {
+    let dir = fixture_dir("edited_id3_tags_are_written_back");
+    let mut tag = Tag::new();
+    tag.set_title("Untitled");
+    tag.set_album("Kid A");
+    tag.set_genre("Electronic");
+    tag.set_track(9);
+    let path = dir.join("track.mp3");
+    let audio = [0xff, 0xfb, 0x90, 0x64, 1, 2, 3, 4];
+    std::fs::write(&path, audio).unwrap();
+    tag.write_to_path(&path, Version::Id3v24).unwrap();
+    let metadata = Track::new(&path).unwrap().metadata().clone();
+
+    // The editor hands over every field; only edited ones count as changes.
+    let mut changes = writer::editable_fields(&metadata);
+    assert_eq!(changes.album.as_deref(), Some("Kid A"));
+    changes.title = Some("Idioteque".into());
+    changes.artist = Some("Radiohead; Paul Lansky".into());
+    changes.track = Some("8/10".into());
+    changes.year = Some("2000".into());
+    changes.comment = Some("Samples Lansky".into());
+    changes.genre = None;
+    changes.album = None;
+    let tags = writer::write_to_path(&path, &changes).unwrap();
+    assert_eq!(tags.metadata.title.as_deref(), Some("Idioteque"));
+
+    let track = Track::new(&path).unwrap();
+    let metadata = track.metadata();
+    assert_eq!(track.name(), "Idioteque");
+    assert_eq!(metadata.artists, ["Radiohead", "Paul Lansky"]);
+    assert_eq!(metadata.track_position().as_deref(), Some("8/10"));
+    assert_eq!(metadata.album_line().as_deref(), Some("Kid A (2000)"));
+    assert_eq!(metadata.genre.as_deref(), Some("Electronic"));
+    assert_eq!(metadata.comment.as_deref(), Some("Samples Lansky"));
+
+    // Empty values remove fields.
+    let clear = TagChanges {
+        comment: Some(String::new()),
+        track: Some(" ".into()),
+        ..TagChanges::default()
+    };
+    writer::write_to_path(&path, &clear).unwrap();
+    let track = Track::new(&path).unwrap();
+    assert_eq!(track.metadata().comment, None);
+    assert_eq!(track.metadata().track_number, None);
+
+    // The audio survives, and the temporary copy is gone.
+    assert!(std::fs::read(&path).unwrap().ends_with(&audio));
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+#[test]
+fn id3v22_tags_are_upgraded_when_written() {
+    let dir = fixture_dir("id3v22_tags_are_upgraded_when_written");
+    let path = dir.join("old.mp3");
+    // ID3v2.2: three-letter frame ids with three-byte sizes.
+    let mut frames = Vec::new();
+    for (id, text) in [(b"TT2", "Old title"), (b"TAL", "Old album")] {
+        frames.extend_from_slice(id);
+        frames.extend_from_slice(&(text.len() as u32 + 1).to_be_bytes()[1..]);
+        frames.push(0);
+        frames.extend_from_slice(text.as_bytes());
+    }
+    let mut file = b"ID3\x02\x00\x00\x00\x00\x00".to_vec();
+    file.push(frames.len() as u8);
+    file.extend_from_slice(&frames);
+    let audio = [0xff, 0xfb, 0x90, 0x64, 1, 2, 3, 4];
+    file.extend_from_slice(&audio);
+    std::fs::write(&path, file).unwrap();
+    assert_eq!(Track::new(&path).unwrap().name(), "Old title");
+
+    let changes = TagChanges {
+        title: Some("New title".into()),
+        ..TagChanges::default()
+    };
+    writer::write_to_path(&path, &changes).unwrap();
+
+    let contents = std::fs::read(&path).unwrap();
+    assert_eq!(&contents[..4], b"ID3\x03");
+    assert!(contents.ends_with(&audio));
+    let track = Track::new(&path).unwrap();
+    assert_eq!(track.name(), "New title");
+    assert_eq!(track.metadata().album.as_deref(), Some("Old album"));
+}
+
+#[test]
+fn edited_flac_comments_are_written_back() {
+    let dir = fixture_dir("edited_flac_comments_are_written_back");
+    let stream_info = [7u8; 34];
+    let comments =
+        vorbis_comment_block(&["TITLE=Wrong", "GENRE=Jazz", "REPLAYGAIN_TRACK_GAIN=-3 dB"]);
+    let picture = flac_picture_block(3, COVER);
+    let audio = b"\xff\xf8 frames";
+    let path = dir.join("track.flac");
+    let blocks: [(u8, &[u8]); 3] = [(0, &stream_info), (4, &comments), (6, &picture)];
+    std::fs::write(&path, flac_file(&blocks, audio)).unwrap();
+
+    let changes = TagChanges {
+        title: Some("So What".into()),
+        artist: Some("Miles Davis".into()),
+        genre: Some(String::new()),
+        track: Some("1/5".into()),
+        ..TagChanges::default()
+    };
+    writer::write_to_path(&path, &changes).unwrap();
+
+    let track = Track::new(&path).unwrap();
+    let metadata = track.metadata();
+    assert_eq!(track.name(), "So What");
+    assert_eq!(track.artist(), "Miles Davis");
+    assert_eq!(metadata.genre, None);
+    assert_eq!(metadata.track_position().as_deref(), Some("1/5"));
+    assert_eq!(track.replay_gain().track_gain, Some(-3.0));
+    assert_eq!(track.extract_img_bytes().unwrap(), COVER);
+
+    let file = std::fs::read(&path).unwrap();
+    // STREAMINFO stays first and untouched; the audio follows the metadata unchanged.
+    assert_eq!(file[4], 0);
+    assert_eq!(&file[8..42], &stream_info);
+    assert!(file.ends_with(audio));
+}
+
+#[test]
+fn unsupported_or_invalid_edits_leave_the_file_alone() {
+    let dir = fixture_dir("unsupported_or_invalid_edits_leave_the_file_alone");
+    let title = TagChanges {
+        title: Some("New".into()),
+        ..TagChanges::default()
+    };
+
+    let m4a = dir.join("track.m4a");
+    let contents = mp4_atom(b"ftyp", b"M4A \0\0\0\0");
+    std::fs::write(&m4a, &contents).unwrap();
+    let error = writer::write_to_path(&m4a, &title).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(std::fs::read(&m4a).unwrap(), contents);
+
+    let mut tag = Tag::new();
+    tag.set_title("Old");
+    let mp3 = tagged_file(&dir, "track.mp3", &tag);
+    let before = std::fs::read(&mp3).unwrap();
+    let bad_year = TagChanges {
+        year: Some("next year".into()),
+        ..title
+    };
+    let error = writer::write_to_path(&mp3, &bad_year).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(std::fs::read(&mp3).unwrap(), before);
+}
+
+#[test]
+fn batch_edits_preview_then_write_shared_fields() {
+    let dir = fixture_dir("batch_edits_preview_then_write_shared_fields");
+    let tracks: Vec<_> = [
+        ("Airbag", Some("OK Computer"), Some(1)),
+        ("Paranoid", Some("OKC"), None),
+        ("Lucky", None, Some(7)),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, (title, album, track))| {
+        let mut tag = Tag::new();
+        tag.set_title(title);
+        tag.set_genre("Rock");
+        if let Some(album) = album {
+            tag.set_album(album);
+        }
+        if let Some(track) = track {
+            tag.set_track(track);
+            tag.set_total_tracks(3);
+        }
+        let path = tagged_file(&dir, &format!("{}.mp3", i), &tag);
+        let metadata = Track::new(&path).unwrap().metadata().clone();
+        (path, metadata)
+    })
+    .collect();
+
+    let metadata: Vec<_> = tracks.iter().map(|(_, m)| m.clone()).collect();
+    let common = BatchEdit::common_values(&metadata);
+    assert_eq!(common.genre.as_deref(), Some("Rock"));
+    assert_eq!(common.album, None);
+
+    let edit = BatchEdit {
+        shared: TagChanges {
+            album: Some("OK Computer".into()),
+            year: Some("1997".into()),
+            ..TagChanges::default()
+        },
+        number_tracks: true,
+    };
+    let plan = edit.plan(&tracks);
+    assert_eq!(plan.len(), 3);
+    // The first track only needs the year; the others get renumbered by list order.
+    assert_eq!(plan[0].changes.album, None);
+    assert_eq!(plan[0].changes.track, None);
+    assert_eq!(
+        plan[1].diff,
+        [
+            FieldChange {
+                field: "Album",
+                old: "OKC".into(),
+                new: "OK Computer".into()
+            },
+            FieldChange {
+                field: "Track",
+                old: String::new(),
+                new: "2/3".into()
+            },
+            FieldChange {
+                field: "Year",
+                old: String::new(),
+                new: "1997".into()
+            },
+        ]
+    );
+    assert_eq!(
+        plan[2].diff[1],
+        FieldChange {
+            field: "Track",
+            old: "7/3".into(),
+            new: "3/3".into()
+        }
+    );
+
+    for planned in &plan {
+        writer::write_to_path(&planned.path, &planned.changes).unwrap();
+    }
+    let written: Vec<_> = tracks
+        .iter()
+        .map(|(path, _)| (path.clone(), Track::new(path).unwrap().metadata().clone()))
+        .collect();
+    for (i, (_, metadata)) in written.iter().enumerate() {
+        assert_eq!(metadata.album.as_deref(), Some("OK Computer"));
+        assert_eq!(metadata.year, Some(1997));
+        assert_eq!(metadata.track_number, Some(i as u32 + 1));
+        assert_eq!(metadata.genre.as_deref(), Some("Rock"));
+    }
+    assert_eq!(written[1].1.title.as_deref(), Some("Paranoid"));
+    // Running the same edit again has nothing left to do.
+    assert!(edit.plan(&written).is_empty());
+}
+
+#[test]
+fn cover_falls_back_to_other_pictures_then_folder_images() {
+    let dir = fixture_dir("cover_falls_back_to_other_pictures_then_folder_images");
+
+    // No front cover embedded: any other picture beats the back cover.
+    let back = flac_picture_block(4, b"back");
+    let leaflet = flac_picture_block(5, b"leaflet");
+    let path = dir.join("embedded.flac");
+    std::fs::write(&path, flac_file(&[(6, &back), (6, &leaflet)], &[])).unwrap();
+    let track = Track::new(&path).unwrap();
+    assert_eq!(track.extract_img_bytes().unwrap(), b"leaflet");
+    let artwork = track.artwork();
+    assert_eq!(artwork.len(), 2);
+
+    // Nothing embedded: the folder image, not just any image lying around.
+    let album = dir.join("album");
+    std::fs::create_dir_all(album.join("Scans")).unwrap();
+    std::fs::write(album.join("Folder.JPG"), b"\xff\xd8folder").unwrap();
+    std::fs::write(album.join("Scans").join("back.png"), COVER).unwrap();
+    std::fs::write(album.join("notes.txt"), b"not an image").unwrap();
+    let path = album.join("01.mp3");
+    std::fs::write(&path, []).unwrap();
+    let track = Track::new(&path).unwrap();
+    assert_eq!(track.extract_img_bytes().unwrap(), b"\xff\xd8folder");
+
+    // The viewer gets every image, front cover first.
+    let artwork = track.artwork();
+    let described: Vec<_> = artwork
+        .iter()
+        .map(|p| (p.kind, p.description.as_str(), p.mime_type.as_str()))
+        .collect();
+    assert_eq!(
+        described,
+        [
+            (PictureKind::FrontCover, "Folder.JPG", "image/jpeg"),
+            (PictureKind::BackCover, "back.png", "image/png"),
+        ]
+    );
+
+    // A stray scan is no cover.
+    std::fs::remove_file(album.join("Folder.JPG")).unwrap();
+    let track = Track::new(&path).unwrap();
+    assert!(track.extract_img_bytes().is_err());
+    assert_eq!(track.artwork().len(), 1);
+}
+
+#[test]
+fn artwork_is_grouped_by_picture_type() {
+    let dir = fixture_dir("artwork_is_grouped_by_picture_type");
+
+    // Lead artist, two booklet pages, the disc and the front cover, out of order.
+    let blocks: Vec<_> = [
+        (7, "artist"),
+        (5, "page 1"),
+        (6, "disc"),
+        (5, "page 2"),
+        (3, "front"),
+    ]
+    .into_iter()
+    .map(|(kind, data)| flac_picture_block(kind, data.as_bytes()))
+    .collect();
+    let blocks: Vec<_> = blocks.iter().map(|block| (6, block.as_slice())).collect();
+    std::fs::create_dir_all(dir.join("Booklet")).unwrap();
+    std::fs::write(dir.join("Booklet").join("03.png"), COVER).unwrap();
+    std::fs::write(dir.join("cd.png"), COVER).unwrap();
+    let path = dir.join("01.flac");
+    std::fs::write(&path, flac_file(&blocks, &[])).unwrap();
+
+    // Embedded pictures show their bytes, folder images their file name.
+    let track = Track::new(&path).unwrap();
+    let artwork = track.artwork();
+    let described: Vec<_> = artwork
+        .iter()
+        .map(|p| match p.data.as_slice() {
+            COVER => (p.kind, p.description.as_str()),
+            data => (p.kind, std::str::from_utf8(data).unwrap()),
+        })
+        .collect();
+    assert_eq!(
+        described,
+        [
+            (PictureKind::FrontCover, "front"),
+            (PictureKind::Leaflet, "page 1"),
+            (PictureKind::Leaflet, "page 2"),
+            (PictureKind::Leaflet, "03.png"),
+            (PictureKind::Media, "disc"),
+            (PictureKind::Media, "cd.png"),
+            (PictureKind::Artist, "artist"),
+        ]
+    );
+}
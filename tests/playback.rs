@@ -0,0 +1,762 @@
+mod common;
+
+use std::{
+    f32::consts::PI,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use id3::{Tag, TagLike, Version, frame::ExtendedText};
+
+use music_player::{
+    audio::{backend::OfflineBackend, equalizer::EqParams, fade::FadeCurve, wav},
+    models::track::MusicState,
+    services::{
+        MusicOpenErrorKind, MusicService,
+        ab_loop::AbLoop,
+        crossfade::CrossfadeSettings,
+        events::PlaybackEvent,
+        player::Player,
+        replay_gain::{ReplayGainMode, ReplayGainSettings},
+        resume_points::ResumeSettings,
+        sleep_timer::{Clock, SleepMode},
+    },
+};
+
+use common::fixture_dir;
+
+const SAMPLE_RATE: u32 = 44_100;
+const CHANNELS: u16 = 2;
+const AMPLITUDE: f32 = 0.5;
+
+/// Writes a stereo sine wave and returns its path.
+fn sine_fixture(dir: &Path, name: &str, frequency: f32, secs: f32) -> PathBuf {
+    let frames = (secs * SAMPLE_RATE as f32) as usize;
+    let samples: Vec<f32> = (0..frames)
+        .flat_map(|n| {
+            let value = AMPLITUDE * (2.0 * PI * frequency * n as f32 / SAMPLE_RATE as f32).sin();
+            [value; CHANNELS as usize]
+        })
+        .collect();
+    let path = dir.join(name);
+    wav::write(&path, CHANNELS, SAMPLE_RATE, &samples).unwrap();
+    path
+}
+
+fn player() -> (MusicService, OfflineBackend) {
+    let backend = OfflineBackend::new(CHANNELS, SAMPLE_RATE);
+    (MusicService::with_backend(backend.clone()), backend)
+}
+
+fn left_channel(samples: &[f32]) -> Vec<f32> {
+    samples.iter().step_by(CHANNELS as usize).copied().collect()
+}
+
+/// Drops the first 100 ms, where the mixer is still settling into a new source.
+fn settled(samples: &[f32]) -> &[f32] {
+    &samples[(SAMPLE_RATE as usize / 10).min(samples.len())..]
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32).sqrt()
+}
+
+/// Frequency estimated from rising zero crossings of one channel.
+fn frequency(samples: &[f32]) -> f32 {
+    let rising = samples
+        .windows(2)
+        .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+        .count();
+    rising as f32 * SAMPLE_RATE as f32 / samples.len() as f32
+}
+
+fn assert_near(actual: Duration, expected: Duration, tolerance: Duration) {
+    let diff = actual.abs_diff(expected);
+    assert!(
+        diff <= tolerance,
+        "expected {expected:?} ± {tolerance:?}, got {actual:?}"
+    );
+}
+
+#[test]
+fn open_renders_the_track() {
+    let dir = fixture_dir("open_renders_the_track");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 2.0);
+    let (mut service, backend) = player();
+
+    service.open(&path).unwrap();
+    assert_eq!(service.state(), MusicState::Playing);
+    assert_eq!(service.get_total_duration().map(|d| d.as_secs()), Some(2));
+
+    let rendered = left_channel(&backend.render(Duration::from_secs(1)));
+    let rendered = settled(&rendered);
+    let expected_rms = AMPLITUDE / 2f32.sqrt();
+    assert!((rms(rendered) - expected_rms).abs() < 0.01, "rms {}", rms(rendered));
+    assert!((frequency(rendered) - 440.0).abs() < 5.0, "freq {}", frequency(rendered));
+
+    assert_near(
+        service.get_pos().unwrap(),
+        Duration::from_secs(1),
+        Duration::from_millis(20),
+    );
+}
+
+#[test]
+fn pause_holds_position_and_resume_continues() {
+    let dir = fixture_dir("pause_holds_position_and_resume_continues");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 3.0);
+    let (mut service, backend) = player();
+
+    service.open(&path).unwrap();
+    backend.render(Duration::from_millis(500));
+    service.pause();
+    assert_eq!(service.state(), MusicState::Paused);
+
+    // The sink picks up control changes every few milliseconds.
+    backend.render(Duration::from_millis(20));
+    let paused_at = service.get_pos().unwrap();
+    let silence = backend.render(Duration::from_millis(500));
+    assert!(rms(&silence) < 1e-4, "rms while paused {}", rms(&silence));
+    assert_eq!(service.get_pos().unwrap(), paused_at);
+
+    service.resume();
+    assert_eq!(service.state(), MusicState::Playing);
+    let resumed = left_channel(&backend.render(Duration::from_millis(500)));
+    assert!(rms(settled(&resumed)) > 0.3);
+    assert_near(
+        service.get_pos().unwrap(),
+        paused_at + Duration::from_millis(500),
+        Duration::from_millis(20),
+    );
+}
+
+/// Two seconds of 220 Hz then two of 880 Hz; the tone tells where playback really is.
+fn low_then_high_fixture(dir: &Path) -> PathBuf {
+    let low = sine_fixture(dir, "low.wav", 220.0, 2.0);
+    let high = sine_fixture(dir, "high.wav", 880.0, 2.0);
+    let path = dir.join("low_then_high.wav");
+    let mut samples = decode_fixture(&low);
+    samples.extend(decode_fixture(&high));
+    wav::write(&path, CHANNELS, SAMPLE_RATE, &samples).unwrap();
+    path
+}
+
+#[test]
+fn seek_jumps_to_the_requested_position() {
+    let dir = fixture_dir("seek_jumps_to_the_requested_position");
+    let path = low_then_high_fixture(&dir);
+
+    let (mut service, backend) = player();
+    service.open(&path).unwrap();
+    let before = left_channel(&backend.render(Duration::from_millis(500)));
+    assert!((frequency(settled(&before)) - 220.0).abs() < 5.0);
+
+    service.set_pos(Duration::from_millis(2500));
+    // Reported straight away, before the audio side has caught up.
+    assert_eq!(service.get_pos(), Some(Duration::from_millis(2500)));
+
+    let after = left_channel(&backend.render(Duration::from_millis(500)));
+    let after = settled(&after);
+    assert!((frequency(after) - 880.0).abs() < 10.0, "freq {}", frequency(after));
+    assert_near(
+        service.get_pos().unwrap(),
+        Duration::from_secs(3),
+        Duration::from_millis(20),
+    );
+}
+
+#[test]
+fn seek_while_paused_applies_on_resume() {
+    let dir = fixture_dir("seek_while_paused_applies_on_resume");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 3.0);
+    let (mut service, backend) = player();
+
+    service.open(&path).unwrap();
+    backend.render(Duration::from_millis(200));
+    service.pause();
+    backend.render(Duration::from_millis(20));
+
+    service.set_pos(Duration::from_secs(2));
+    assert_eq!(service.get_pos(), Some(Duration::from_secs(2)));
+
+    service.resume();
+    backend.render(Duration::from_millis(100));
+    assert_near(
+        service.get_pos().unwrap(),
+        Duration::from_millis(2100),
+        Duration::from_millis(20),
+    );
+}
+
+/// Largest jump between consecutive samples; a click shows up as a spike.
+fn max_step(samples: &[f32]) -> f32 {
+    samples
+        .windows(2)
+        .map(|w| (w[1] - w[0]).abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn transport_actions_ramp_instead_of_clicking() {
+    let dir = fixture_dir("transport_actions_ramp_instead_of_clicking");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 3.0);
+    let (mut service, backend) = player();
+    // Steepest slope of the sine itself, with a little headroom.
+    let smooth = 2.0 * PI * 440.0 * AMPLITUDE / SAMPLE_RATE as f32 * 1.5;
+
+    service.open(&path).unwrap();
+    backend.render(Duration::from_millis(300));
+
+    let mut rendered = Vec::new();
+    let mut render = |ms| rendered.extend(left_channel(&backend.render(Duration::from_millis(ms))));
+    service.pause();
+    render(50);
+    service.resume();
+    render(50);
+    service.set_pos(Duration::from_millis(1234));
+    render(50);
+    service.stop();
+    render(50);
+
+    assert!(max_step(&rendered) < smooth, "step {} > {}", max_step(&rendered), smooth);
+    assert!(rms(&rendered[rendered.len() - 100..]) < 1e-4);
+}
+
+#[test]
+fn fine_seek_keeps_millisecond_precision_and_clamps() {
+    let dir = fixture_dir("fine_seek_keeps_millisecond_precision_and_clamps");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 3.0);
+    let (mut service, _backend) = player();
+    service.open(&path).unwrap();
+
+    service.set_pos(Duration::from_millis(1234));
+    service.seek_forward(Duration::from_secs(1));
+    assert_eq!(service.get_pos(), Some(Duration::from_millis(2234)));
+
+    service.seek_backward(Duration::from_secs(5));
+    assert_eq!(service.get_pos(), Some(Duration::ZERO));
+
+    service.seek_forward(Duration::from_secs(30));
+    assert_eq!(service.get_pos(), service.get_total_duration());
+}
+
+#[test]
+fn left_off_tracks_offer_to_resume() {
+    let dir = fixture_dir("left_off_tracks_offer_to_resume");
+    let lecture = sine_fixture(&dir, "lecture.wav", 440.0, 20.0);
+    let other = sine_fixture(&dir, "other.wav", 220.0, 20.0);
+    let (mut service, backend) = player();
+    service.set_resume_settings(ResumeSettings {
+        enabled: true,
+        min_length: Duration::from_secs(10),
+    });
+
+    service.open(&lecture).unwrap();
+    assert_eq!(service.resume_offer(), None);
+    service.set_pos(Duration::from_secs(7));
+    service.pause();
+
+    service.open(&other).unwrap();
+    assert_eq!(service.resume_offer(), None);
+    service.open(&lecture).unwrap();
+    assert_eq!(service.resume_offer(), Some(Duration::from_secs(7)));
+
+    service.accept_resume_offer();
+    assert_eq!(service.resume_offer(), None);
+    backend.render(Duration::from_millis(100));
+    assert_near(
+        service.get_pos().unwrap(),
+        Duration::from_millis(7100),
+        Duration::from_millis(20),
+    );
+
+    // Leaving off just before the end counts as finished.
+    service.set_pos(Duration::from_secs(18));
+    service.stop();
+    service.open(&lecture).unwrap();
+    assert_eq!(service.resume_offer(), None);
+
+    // Tracks under the minimum length always start over.
+    service.set_pos(Duration::from_secs(7));
+    service.set_resume_settings(ResumeSettings {
+        enabled: true,
+        min_length: Duration::from_secs(60),
+    });
+    service.stop();
+    service.open(&lecture).unwrap();
+    assert_eq!(service.resume_offer(), None);
+}
+
+#[test]
+fn switching_devices_keeps_resume_offers_as_they_were() {
+    let dir = fixture_dir("switching_devices_keeps_resume_offers_as_they_were");
+    let lecture = sine_fixture(&dir, "lecture.wav", 440.0, 20.0);
+    let other = sine_fixture(&dir, "other.wav", 220.0, 20.0);
+    let (mut service, _) = player();
+    let swap = |service: &mut MusicService| {
+        service.set_backend(OfflineBackend::new(CHANNELS, SAMPLE_RATE));
+    };
+    service.set_resume_settings(ResumeSettings {
+        enabled: true,
+        min_length: Duration::from_secs(10),
+    });
+
+    // Picking the track back up on another device doesn't offer to jump anywhere.
+    service.open(&lecture).unwrap();
+    service.set_pos(Duration::from_secs(7));
+    swap(&mut service);
+    assert_eq!(service.state(), MusicState::Playing);
+    assert_eq!(service.resume_offer(), None);
+
+    // An unanswered offer survives the swap, and stays gone once dismissed.
+    service.open(&other).unwrap();
+    service.open(&lecture).unwrap();
+    assert_eq!(service.resume_offer(), Some(Duration::from_secs(7)));
+    swap(&mut service);
+    assert_eq!(service.resume_offer(), Some(Duration::from_secs(7)));
+    service.dismiss_resume_offer();
+    swap(&mut service);
+    assert_eq!(service.resume_offer(), None);
+
+    // Paused partway in, it stays paused there, quietly, and keeps its resume point.
+    service.set_pos(Duration::from_secs(12));
+    service.pause();
+    let events = service.subscribe();
+    swap(&mut service);
+    assert_eq!(service.state(), MusicState::Paused);
+    assert_eq!(service.get_pos(), Some(Duration::from_secs(12)));
+    assert_eq!(events.try_iter().count(), 0);
+    service.open(&other).unwrap();
+    service.open(&lecture).unwrap();
+    assert_eq!(service.resume_offer(), Some(Duration::from_secs(12)));
+}
+
+#[test]
+fn stop_silences_and_rewinds() {
+    let dir = fixture_dir("stop_silences_and_rewinds");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 2.0);
+    let (mut service, backend) = player();
+
+    service.open(&path).unwrap();
+    backend.render(Duration::from_millis(300));
+    service.stop();
+
+    assert_eq!(service.state(), MusicState::Stopped);
+    assert_eq!(service.get_pos(), Some(Duration::ZERO));
+    backend.render(Duration::from_millis(20));
+    let rendered = backend.render(Duration::from_millis(300));
+    assert!(rms(&rendered) < 1e-4, "rms after stop {}", rms(&rendered));
+
+    // Play after stop starts the track over.
+    service.resume();
+    assert_eq!(service.state(), MusicState::Playing);
+    backend.render(Duration::from_millis(100));
+    assert_near(
+        service.get_pos().unwrap(),
+        Duration::from_millis(100),
+        Duration::from_millis(20),
+    );
+}
+
+#[test]
+fn finished_track_advances_through_the_queue() {
+    let dir = fixture_dir("finished_track_advances_through_the_queue");
+    let first = sine_fixture(&dir, "1.wav", 440.0, 0.5);
+    let second = sine_fixture(&dir, "2.wav", 660.0, 0.5);
+    let (mut service, backend) = player();
+
+    service.play_tracks(vec![first, second.clone()], 0).unwrap();
+    for _ in 0..20 {
+        backend.render(Duration::from_millis(50));
+        service.update();
+    }
+    assert_eq!(service.current_index(), Some(1));
+    assert_eq!(service.music_file.path(), second.as_path());
+
+    for _ in 0..20 {
+        backend.render(Duration::from_millis(50));
+        service.update();
+    }
+    assert_eq!(service.state(), MusicState::Completed);
+}
+
+#[test]
+fn sleep_timer_fades_out_then_pauses() {
+    let dir = fixture_dir("sleep_timer_fades_out_then_pauses");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 3.0);
+    let (mut service, backend) = player();
+    let clock = Clock::manual();
+    service.set_clock(clock.clone());
+    service.open(&path).unwrap();
+    let full = rms(settled(&left_channel(&backend.render(Duration::from_millis(300)))));
+
+    service.set_sleep_timer(SleepMode::After(Duration::from_millis(400)));
+    service.update();
+    clock.advance(Duration::from_millis(250));
+    service.update();
+    let fading = rms(&left_channel(&backend.render(Duration::from_millis(50))));
+    assert!(fading < full * 0.5, "rms {} while fading, {} before", fading, full);
+
+    clock.advance(Duration::from_millis(200));
+    service.update();
+    assert_eq!(service.state(), MusicState::Paused);
+    assert!(service.sleep_timer().is_none());
+
+    // Back at full volume when playback resumes.
+    service.resume();
+    backend.render(Duration::from_millis(20));
+    let resumed = rms(&left_channel(&backend.render(Duration::from_millis(50))));
+    assert!((resumed - full).abs() < 0.02, "rms {} after resuming", resumed);
+}
+
+#[test]
+fn sleep_timer_stops_at_the_end_of_the_track() {
+    let dir = fixture_dir("sleep_timer_stops_at_the_end_of_the_track");
+    let first = sine_fixture(&dir, "1.wav", 440.0, 0.5);
+    let second = sine_fixture(&dir, "2.wav", 660.0, 0.5);
+    let (mut service, backend) = player();
+
+    service.play_tracks(vec![first, second.clone()], 0).unwrap();
+    service.set_sleep_timer(SleepMode::EndOfTrack);
+    for _ in 0..20 {
+        backend.render(Duration::from_millis(50));
+        service.update();
+    }
+    // The next track is ready to go, but waits for the listener to come back.
+    assert_eq!(service.current_index(), Some(1));
+    assert_eq!(service.state(), MusicState::Paused);
+    assert_eq!(service.get_pos(), Some(Duration::ZERO));
+    assert!(service.sleep_timer().is_none());
+}
+
+#[test]
+fn render_to_wav_round_trips() {
+    let dir = fixture_dir("render_to_wav_round_trips");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 1.0);
+    let out = dir.join("rendered.wav");
+    let (mut service, backend) = player();
+
+    service.open(&path).unwrap();
+    backend.render_to_wav(&out, Duration::from_millis(500)).unwrap();
+
+    let rendered = left_channel(&decode_fixture(&out));
+    assert_eq!(rendered.len(), SAMPLE_RATE as usize / 2);
+    assert!((frequency(settled(&rendered)) - 440.0).abs() < 5.0);
+}
+
+fn decode_fixture(path: &Path) -> Vec<f32> {
+    let file = std::fs::File::open(path).unwrap();
+    rodio::Decoder::try_from(file).unwrap().collect()
+}
+
+#[test]
+fn open_errors_carry_kind_path_and_cause() {
+    let dir = fixture_dir("open_errors_carry_kind_path_and_cause");
+    let (mut service, _backend) = player();
+
+    let missing = dir.join("missing.wav");
+    let error = service.open(&missing).unwrap_err();
+    assert_eq!(error.kind(), MusicOpenErrorKind::FileMissing);
+    assert_eq!(error.path(), Some(missing.as_path()));
+    assert!(std::error::Error::source(&error).is_some());
+    assert_eq!(error.to_string(), "missing.wav: file not found");
+
+    let garbage = dir.join("notes.txt");
+    std::fs::write(&garbage, "not audio at all").unwrap();
+    let error = service.open(&garbage).unwrap_err();
+    assert_eq!(error.kind(), MusicOpenErrorKind::UnsupportedFormat);
+    assert_eq!(service.last_error().map(|e| e.kind()), Some(error.kind()));
+}
+
+#[test]
+fn events_follow_the_transport() {
+    let dir = fixture_dir("events_follow_the_transport");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 1.0);
+    let (mut service, backend) = player();
+    let events = service.subscribe();
+
+    service.open(&path).unwrap();
+    backend.render(Duration::from_millis(300));
+    service.update();
+    service.pause();
+    service.set_pos(Duration::from_millis(900));
+    service.resume();
+    for _ in 0..10 {
+        backend.render(Duration::from_millis(50));
+        service.update();
+    }
+
+    let received: Vec<PlaybackEvent> = events.try_iter().collect();
+    let names: Vec<&str> = received
+        .iter()
+        .map(|event| match event {
+            PlaybackEvent::TrackLoaded { .. } => "loaded",
+            PlaybackEvent::Playing => "playing",
+            PlaybackEvent::Paused => "paused",
+            PlaybackEvent::Stopped => "stopped",
+            PlaybackEvent::Seeked(_) => "seeked",
+            PlaybackEvent::PositionTick(_) => "tick",
+            PlaybackEvent::TrackFinished { .. } => "finished",
+            PlaybackEvent::Error(_) => "error",
+        })
+        .collect();
+    assert_eq!(
+        names,
+        ["loaded", "playing", "tick", "paused", "seeked", "playing", "finished"]
+    );
+
+    match &received[0] {
+        PlaybackEvent::TrackLoaded { path: loaded, duration, .. } => {
+            assert_eq!(loaded, &path);
+            assert_eq!(duration.map(|d| d.as_secs()), Some(1));
+        }
+        other => panic!("expected TrackLoaded, got {other:?}"),
+    }
+    assert!(matches!(received[4], PlaybackEvent::Seeked(pos) if pos == Duration::from_millis(900)));
+}
+
+#[test]
+fn player_opens_tracks_on_its_own_thread() {
+    let dir = fixture_dir("player_opens_tracks_on_its_own_thread");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 1.0);
+    let backend = OfflineBackend::new(CHANNELS, SAMPLE_RATE);
+    let mut player = Player::spawn(move || MusicService::with_backend(backend));
+
+    player.play_tracks(vec![path.clone()], 0);
+    assert_eq!(player.loading(), Some(path.as_path()));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while player.loading().is_some() || !player.view().music_loaded {
+        assert!(Instant::now() < deadline, "track never finished loading");
+        std::thread::sleep(Duration::from_millis(5));
+        player.poll();
+    }
+    assert_eq!(player.view().current_path.as_deref(), Some(path.as_path()));
+    assert_eq!(player.view().state, MusicState::Playing);
+
+    // Setters show up in the view straight away, before the audio thread runs them.
+    player.set_volume(0.25);
+    assert_eq!(player.view().volume.level(), 0.25);
+}
+
+#[test]
+fn player_view_keeps_updating_while_commands_stream_in() {
+    let dir = fixture_dir("player_view_keeps_updating_while_commands_stream_in");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 1.0);
+    let backend = OfflineBackend::new(CHANNELS, SAMPLE_RATE);
+    let mut player = Player::spawn(move || MusicService::with_backend(backend));
+    player.play_tracks(vec![path], 0);
+
+    // Like dragging the volume slider: a new command every frame.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut level = 0.5;
+    while !player.view().music_loaded {
+        assert!(Instant::now() < deadline, "view stopped updating");
+        level = if level == 0.5 { 0.75 } else { 0.5 };
+        player.set_volume(level);
+        std::thread::sleep(Duration::from_millis(5));
+        player.poll();
+        assert_eq!(player.view().volume.level(), level);
+    }
+    assert_eq!(player.view().state, MusicState::Playing);
+}
+
+#[test]
+fn ab_loop_wraps_around_to_a() {
+    let dir = fixture_dir("ab_loop_wraps_around_to_a");
+    let path = low_then_high_fixture(&dir);
+    let (mut service, backend) = player();
+    let (a, b) = (Duration::from_millis(1000), Duration::from_millis(1500));
+
+    service.open(&path).unwrap();
+    service.set_ab_loop(AbLoop {
+        a: Some(a),
+        b: Some(b),
+    });
+    service.set_pos(a);
+
+    // Two seconds of playback go round the half-second loop several times.
+    let mut rendered = Vec::new();
+    let mut wraps = 0;
+    let mut last = a;
+    for _ in 0..40 {
+        rendered.extend(left_channel(&backend.render(Duration::from_millis(50))));
+        service.update();
+        let pos = service.get_pos().unwrap();
+        assert!(pos >= a && pos <= b + Duration::from_millis(50), "pos {pos:?}");
+        if pos < last {
+            wraps += 1;
+        }
+        last = pos;
+    }
+    assert!(wraps >= 3, "wrapped {wraps} times");
+    assert_eq!(service.state(), MusicState::Playing);
+
+    // Never past B into the high half of the file.
+    let rendered = settled(&rendered);
+    assert!((frequency(rendered) - 220.0).abs() < 5.0, "freq {}", frequency(rendered));
+}
+
+/// Amplitude of the `frequency` component of one channel.
+fn tone_level(samples: &[f32], frequency: f32) -> f32 {
+    let step = 2.0 * PI * frequency / SAMPLE_RATE as f32;
+    let (re, im) = samples.iter().enumerate().fold((0.0, 0.0), |(re, im), (n, s)| {
+        let phase = step * n as f32;
+        (re + s * phase.cos(), im + s * phase.sin())
+    });
+    2.0 * (re * re + im * im).sqrt() / samples.len() as f32
+}
+
+#[test]
+fn crossfade_overlaps_the_outgoing_and_incoming_tracks() {
+    let dir = fixture_dir("crossfade_overlaps_the_outgoing_and_incoming_tracks");
+    let first = sine_fixture(&dir, "1.wav", 440.0, 2.0);
+    let second = sine_fixture(&dir, "2.wav", 660.0, 2.0);
+    let (mut service, backend) = player();
+    service.set_crossfade(CrossfadeSettings {
+        duration: Duration::from_secs(1),
+        curve: FadeCurve::Linear,
+    });
+
+    // 100 ms windows hold a whole number of cycles of both tones.
+    service.play_tracks(vec![first, second], 0).unwrap();
+    let mut windows = Vec::new();
+    for _ in 0..30 {
+        windows.push(left_channel(&backend.render(Duration::from_millis(100))));
+        service.update();
+    }
+    let levels = |window: &[f32]| (tone_level(window, 440.0), tone_level(window, 660.0));
+
+    let (first_level, second_level) = levels(&windows[5]);
+    assert!(first_level > 0.45 && second_level < 0.01, "before: {first_level} {second_level}");
+
+    // Halfway through the last second of the first track both are playing.
+    let (first_level, second_level) = levels(&windows[15]);
+    assert!(first_level > 0.15 && first_level < 0.35, "halfway: {first_level}");
+    assert!(second_level > 0.15 && second_level < 0.35, "halfway: {second_level}");
+    assert_eq!(service.current_index(), Some(1));
+
+    let (first_level, second_level) = levels(&windows[25]);
+    assert!(first_level < 0.01 && second_level > 0.45, "after: {first_level} {second_level}");
+}
+
+#[test]
+fn muting_takes_the_gain_to_zero_and_volume_follows_the_curve() {
+    let dir = fixture_dir("muting_takes_the_gain_to_zero_and_volume_follows_the_curve");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 3.0);
+    let (mut service, backend) = player();
+    let full = AMPLITUDE / 2f32.sqrt();
+
+    service.open(&path).unwrap();
+    backend.render(Duration::from_millis(300));
+
+    service.set_muted(true);
+    assert_eq!(service.volume().gain(), 0.0);
+    // The sink picks up volume changes every few milliseconds.
+    backend.render(Duration::from_millis(20));
+    let muted = backend.render(Duration::from_millis(300));
+    assert!(rms(&muted) < 1e-4, "rms while muted {}", rms(&muted));
+
+    // Moving the slider unmutes; half way is an eighth of the amplitude.
+    service.set_volume(0.5);
+    assert!(!service.volume().is_muted());
+    backend.render(Duration::from_millis(20));
+    let quiet = rms(&left_channel(&backend.render(Duration::from_millis(300))));
+    assert!((quiet - full * 0.125).abs() < 0.005, "rms {} at half volume", quiet);
+}
+
+#[test]
+fn gapless_preload_leaves_no_silence_between_tracks() {
+    let dir = fixture_dir("gapless_preload_leaves_no_silence_between_tracks");
+    let first = sine_fixture(&dir, "1.wav", 440.0, 1.0);
+    let second = sine_fixture(&dir, "2.wav", 440.0, 1.0);
+    let (mut service, backend) = player();
+
+    service.play_tracks(vec![first, second], 0).unwrap();
+    let mut rendered = Vec::new();
+    for _ in 0..36 {
+        rendered.extend(left_channel(&backend.render(Duration::from_millis(50))));
+        service.update();
+    }
+    assert_eq!(service.current_index(), Some(1));
+
+    // A sine only brushes zero; a gap shows up as a run of silent samples.
+    let mut longest_silence = 0;
+    let mut silence = 0;
+    for sample in settled(&rendered) {
+        silence = if sample.abs() < 1e-3 { silence + 1 } else { 0 };
+        longest_silence = longest_silence.max(silence);
+    }
+    assert!(longest_silence < 3, "{longest_silence} silent samples");
+}
+
+#[test]
+fn replay_gain_levels_tagged_tracks() {
+    let dir = fixture_dir("replay_gain_levels_tagged_tracks");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 3.0);
+    let mut tag = Tag::new();
+    tag.add_frame(ExtendedText {
+        description: String::from("REPLAYGAIN_TRACK_GAIN"),
+        value: String::from("-6.02 dB"),
+    });
+    tag.write_to_path(&path, Version::Id3v24).unwrap();
+    let (mut service, backend) = player();
+    let full = AMPLITUDE / 2f32.sqrt();
+
+    service.set_replay_gain(ReplayGainSettings {
+        mode: ReplayGainMode::Track,
+        preamp_db: 0.0,
+    });
+    service.open(&path).unwrap();
+    let levelled = rms(settled(&left_channel(&backend.render(Duration::from_millis(300)))));
+    assert!((levelled - full * 0.5).abs() < 0.005, "rms {} at -6 dB", levelled);
+
+    // Switching it off applies to the playing track straight away.
+    service.set_replay_gain(ReplayGainSettings::default());
+    backend.render(Duration::from_millis(20));
+    let unchanged = rms(&left_channel(&backend.render(Duration::from_millis(300))));
+    assert!((unchanged - full).abs() < 0.005, "rms {} with ReplayGain off", unchanged);
+}
+
+#[test]
+fn equalizer_cuts_the_band_of_the_tone() {
+    let dir = fixture_dir("equalizer_cuts_the_band_of_the_tone");
+    let path = sine_fixture(&dir, "a1000.wav", 1000.0, 3.0);
+    let (mut service, backend) = player();
+    let full = AMPLITUDE / 2f32.sqrt();
+
+    let mut params = EqParams {
+        enabled: true,
+        ..EqParams::default()
+    };
+    let band = params.bands.iter().position(|band| band.frequency == 1000.0).unwrap();
+    params.bands[band].gain_db = -12.0;
+    service.set_equalizer_params(params);
+    service.open(&path).unwrap();
+    let cut = rms(settled(&left_channel(&backend.render(Duration::from_millis(300)))));
+    assert!((cut - full * 0.251).abs() < 0.01, "rms {} at -12 dB", cut);
+
+    // Turning it off takes effect mid-track.
+    service.set_equalizer_params(EqParams::default());
+    backend.render(Duration::from_millis(50));
+    let flat = rms(&left_channel(&backend.render(Duration::from_millis(300))));
+    assert!((flat - full).abs() < 0.01, "rms {} with the equalizer off", flat);
+}
+
+#[test]
+fn speed_changes_tempo_but_not_pitch() {
+    let dir = fixture_dir("speed_changes_tempo_but_not_pitch");
+    let path = sine_fixture(&dir, "a440.wav", 440.0, 4.0);
+    let (mut service, backend) = player();
+
+    service.open(&path).unwrap();
+    service.set_speed(1.5);
+    let rendered = left_channel(&backend.render(Duration::from_secs(1)));
+    let rendered = settled(&rendered);
+    assert!((frequency(rendered) - 440.0).abs() < 10.0, "freq {}", frequency(rendered));
+    assert_near(
+        service.get_pos().unwrap(),
+        Duration::from_millis(1500),
+        Duration::from_millis(100),
+    );
+}